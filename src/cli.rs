@@ -2,17 +2,35 @@ use clap::{Parser, Subcommand};
 
 use crate::io::ReadSource;
 
+pub mod config;
+pub mod convert;
+pub mod edit;
+pub mod import_timestamps;
+pub mod import_transcript;
+pub mod merge;
+pub mod migrate;
 pub mod plot;
 pub mod record;
+pub mod restore;
+pub mod serve_record;
 pub mod stats;
+pub mod validate;
 
 #[derive(Parser, Debug)]
 #[command(arg_required_else_help = true)]
 pub struct Args {
+    /// The data file. `stats` and `plot` read several files at once: repeat the option or
+    /// give a directory or a pattern like `team/*.json`.
     #[arg(short = 'f', long = "file", default_value = "uhm.json")]
-    from: String,
+    from: Vec<String>,
     #[arg(long = "stdin", action = clap::ArgAction::SetTrue)]
     stdin: bool,
+    /// How the data file is stored. Detected from its contents or extension by default.
+    #[arg(long = "store", value_enum)]
+    store: Option<utils::Storage>,
+    /// The configuration file. Defaults to `~/.config/uhm/config.toml`.
+    #[arg(long = "config", value_name = "PATH")]
+    config: Option<String>,
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -22,29 +40,75 @@ pub enum Commands {
     Record(record::Args),
     Stats(stats::Args),
     Plot(plot::Args),
+    Merge(merge::Args),
+    ServeRecord(serve_record::Args),
+    Config(config::Args),
+    ImportTimestamps(import_timestamps::Args),
+    ImportTranscript(import_transcript::Args),
+    Migrate(migrate::Args),
+    Edit(edit::Args),
+    Validate(validate::Args),
+    Convert(convert::Args),
+    Restore(restore::Args),
 }
 
 pub fn run(args: Args) {
-    let source = if args.stdin {
-        ReadSource::Stdin
+    let sources = if args.stdin {
+        vec![ReadSource::Stdin]
     } else {
-        ReadSource::File(args.from)
+        utils::data_files(&args.from)
+            .into_iter()
+            .map(ReadSource::File)
+            .collect()
     };
+    let storage = args.store;
+    // all commands except for reading ones change a single data file
+    let source = || match sources.as_slice() {
+        [source] => source.clone(),
+        _ => crate::cli_exit!(
+            "This command works on a single data file, but {} were given",
+            sources.len()
+        ),
+    };
+    let config = utils::load_config(args.config.as_deref());
     match args.command {
         None => {
             Args::parse_from(["--help"]);
         }
         Some(command) => match command {
-            Commands::Record(args) => record::run(source, args),
-            Commands::Stats(args) => stats::run(source, args),
-            Commands::Plot(args) => plot::run(source, args),
+            Commands::Record(args) => record::run(source(), storage, &config, args),
+            Commands::Stats(args) => stats::run(&sources, storage, args),
+            Commands::Plot(args) => plot::run(&sources, storage, args),
+            Commands::Merge(args) => merge::run(source(), storage, args),
+            Commands::ServeRecord(args) => serve_record::run(source(), storage, &config, args),
+            Commands::Config(args) => config::run(&config, args),
+            Commands::ImportTimestamps(args) => {
+                import_timestamps::run(source(), storage, &config, args)
+            }
+            Commands::ImportTranscript(args) => {
+                import_transcript::run(source(), storage, &config, args)
+            }
+            Commands::Migrate(args) => migrate::run(source(), storage, args),
+            Commands::Edit(args) => edit::run(source(), storage, args),
+            Commands::Validate(args) => validate::run(source(), storage, args),
+            Commands::Convert(args) => convert::run(source(), storage, args),
+            Commands::Restore(args) => restore::run(source(), storage, args),
         },
     };
 }
 
 mod utils {
+    use chrono::{DateTime, Utc};
+
     use crate::Uhms;
-    use crate::io::{ReadSource, WriteTarget};
+    use crate::config::Config;
+    use crate::io::{FileLock, ReadSource, WriteTarget};
+    use crate::query::Query;
+    use crate::schema::Format;
+    use crate::store::{FileStore, MemoryStore, MultiStore, Store};
+
+    /// The number of backups kept of a data file when it is rewritten.
+    pub const BACKUPS: usize = 3;
 
     #[macro_export]
     macro_rules! cli_exit {
@@ -57,42 +121,502 @@ mod utils {
         }};
     }
 
-    pub fn read_file(source: &ReadSource) -> Result<Vec<Uhms>, String> {
+    /// Read the configuration from `path`, or from the default location if it exists.
+    pub fn load_config(path: Option<&str>) -> Config {
+        let path = match path {
+            Some(path) => path.to_string(),
+            None => match Config::default_path() {
+                Some(path) if std::path::Path::new(&path).is_file() => path,
+                _ => return Config::default(),
+            },
+        };
+        match Config::load(&path) {
+            Ok(config) => config,
+            Err(e) => cli_exit!("{}", e),
+        }
+    }
+
+    /// The filler phrases given on the command line, or the configured ones if there are none.
+    pub fn lexicon(config: &Config, fillers: &[String]) -> crate::transcript::Lexicon {
+        if fillers.is_empty() {
+            return config.lexicon.clone();
+        }
+        let mut lexicon = crate::transcript::Lexicon::new();
+        for filler in fillers {
+            lexicon.insert_entry(filler);
+        }
+        lexicon
+    }
+
+    /// Open the data file at `path` in the given storage, or in its current one, see
+    /// [detect_storage]. Files of older schema versions are migrated when they are read.
+    pub fn open_store(path: &str, storage: Option<Storage>) -> Result<Box<dyn Store>, String> {
+        open_store_as(path, storage.unwrap_or_else(|| detect_storage(path)))
+    }
+
+    /// Open the data file at `path` in the given storage. Changes keep backups of it.
+    pub fn open_store_as(path: &str, storage: Storage) -> Result<Box<dyn Store>, String> {
+        let format = match storage {
+            Storage::Json => Format::Json,
+            Storage::Jsonl => Format::Lines,
+            Storage::Sqlite => return open_database(path),
+        };
+        match FileStore::open(path) {
+            Ok(store) => Ok(Box::new(store.format(format).backups(BACKUPS))),
+            Err(e) => Err(format!("Cannot read from {}: {}", path, e)),
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn open_database(path: &str) -> Result<Box<dyn Store>, String> {
+        match crate::sqlite::Database::open(path) {
+            Ok(database) => Ok(Box::new(database.backups(BACKUPS))),
+            Err(e) => Err(format!("Cannot read from {}: {}", path, e)),
+        }
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn open_database(path: &str) -> Result<Box<dyn Store>, String> {
+        Err(format!(
+            "Cannot read from {}: uhm was built without SQLite support",
+            path
+        ))
+    }
+
+    /// Open the recordings to read from, see [open_store]. Data read from stdin is kept in
+    /// memory.
+    pub fn open_source(
+        source: &ReadSource,
+        storage: Option<Storage>,
+    ) -> Result<Box<dyn Store>, String> {
+        if let ReadSource::File(path) = source {
+            if !std::path::Path::new(path).is_file() {
+                return Err(format!("Cannot read from {}: no such file", path));
+            }
+            return open_store(path, storage);
+        }
+
         let content = match source.read() {
             Ok(content) => content,
-            Err(e) => cli_exit!("Cannot read from {}: {}", source.map("stdin", |f| f), e),
+            Err(e) => return Err(format!("Cannot read from stdin: {}", e)),
         };
+        match crate::schema::parse(&content) {
+            Ok((document, version)) => Ok(Box::new(
+                MemoryStore::new(document.recordings).with_version(version),
+            )),
+            Err(e) => Err(format!("Cannot parse input from stdin: {}", e)),
+        }
+    }
 
-        match serde_json::from_str(&content) {
-            Ok(content) => Ok(content),
-            Err(e) => cli_exit!(
-                "Cannot parse input from {}: {}",
-                source.map("stdin", |f| f),
-                e
-            ),
+    /// Open several sources to read from as one, keeping the path of each for display.
+    pub fn open_sources(
+        sources: &[ReadSource],
+        storage: Option<Storage>,
+    ) -> Result<MultiStore, String> {
+        let mut stores = MultiStore::default();
+        for source in sources {
+            stores = stores.with(source.map("stdin", |f| f), open_source(source, storage)?);
+        }
+        Ok(stores)
+    }
+
+    /// Whether changes of `source` are written back to it.
+    fn writes_back(source: &ReadSource, target: &WriteTarget) -> bool {
+        target.map(false, |to| source.map(false, |from| from == to))
+    }
+
+    /// Open the recordings of `source` to change them. Changes written back to the data
+    /// file are made in it directly, all others are kept in memory until [save] writes
+    /// them to `target`. A missing data file has no recordings.
+    pub fn open_changes(
+        source: &ReadSource,
+        target: &WriteTarget,
+        storage: Option<Storage>,
+    ) -> Result<Box<dyn Store>, String> {
+        if let ReadSource::File(path) = source {
+            if writes_back(source, target) {
+                return open_store(path, storage);
+            }
+            if !std::path::Path::new(path).is_file() {
+                return Ok(Box::new(MemoryStore::default()));
+            }
+        }
+
+        let store = open_source(source, storage)?;
+        let version = store.version()?;
+        Ok(Box::new(
+            MemoryStore::new(store.list()?).with_version(version),
+        ))
+    }
+
+    /// Write the recordings changed in a store opened with [open_changes] to `target` in the
+    /// given storage, replacing all recordings there.
+    pub fn save(
+        source: &ReadSource,
+        target: &WriteTarget,
+        storage: Option<Storage>,
+        store: &dyn Store,
+    ) -> Result<(), String> {
+        if writes_back(source, target) {
+            return Ok(());
+        }
+        let recordings = store.list()?;
+        match target {
+            WriteTarget::File(path) => open_store(path, storage)?.replace(recordings),
+            WriteTarget::Stdout => {
+                let formatted = Format::Json
+                    .to_string(&recordings)
+                    .map_err(|e| e.to_string())?;
+                target
+                    .write(&formatted)
+                    .map_err(|e| format!("Cannot write to stdout: {}", e))
+            }
+        }
+    }
+
+    /// Add a recording to a store opened with [open_changes] and save it. Returns the ID of
+    /// the recording.
+    pub fn add(
+        source: &ReadSource,
+        target: &WriteTarget,
+        storage: Option<Storage>,
+        store: &mut dyn Store,
+        recording: Uhms,
+    ) -> Result<String, String> {
+        let id = store.insert(recording)?;
+        save(source, target, storage, store)?;
+        Ok(id)
+    }
+
+    /// Select recordings by ID, unique ID prefix, index starting at 1 or name. Numbers
+    /// are taken as an index if there is a recording at it and as an ID prefix otherwise.
+    /// Every selector may match several recordings with the same name. If no selectors are
+    /// given, all recordings are selected.
+    pub fn select<'a>(data: &'a [Uhms], selectors: &[String]) -> Result<Vec<&'a Uhms>, String> {
+        Ok(select_indices(data, selectors)?
+            .into_iter()
+            .map(|i| &data[i])
+            .collect())
+    }
+
+    /// Like [select], but returns the positions of the selected recordings in `data`.
+    pub fn select_indices(data: &[Uhms], selectors: &[String]) -> Result<Vec<usize>, String> {
+        if selectors.is_empty() {
+            return Ok((0..data.len()).collect());
+        }
+
+        let positions = |matches: &dyn Fn(&Uhms) -> bool| -> Vec<usize> {
+            data.iter()
+                .enumerate()
+                .filter(|(_, u)| matches(u))
+                .map(|(i, _)| i)
+                .collect()
+        };
+        let mut selected: Vec<usize> = Vec::new();
+        for selector in selectors {
+            let matches: Vec<usize> = if let Some(i) = data.iter().position(|u| u.id == *selector) {
+                vec![i]
+            } else if data.iter().any(|u| u.name.as_ref() == Some(selector)) {
+                positions(&|u| u.name.as_ref() == Some(selector))
+            } else if let Some(i) = selector
+                .parse::<usize>()
+                .ok()
+                .filter(|i| (1..=data.len()).contains(i))
+            {
+                vec![i - 1]
+            } else {
+                // all-digit selectors out of range as an index may still prefix an ID
+                let prefixed = positions(&|u| u.id.starts_with(selector.as_str()));
+                if prefixed.len() > 1 {
+                    return Err(format!("ID prefix '{}' is ambiguous", selector));
+                }
+                if let (true, Ok(index)) = (prefixed.is_empty(), selector.parse::<usize>()) {
+                    return Err(format!(
+                        "No recording at index {}, there are {}",
+                        index,
+                        data.len()
+                    ));
+                }
+                prefixed
+            };
+
+            if matches.is_empty() {
+                return Err(format!("No recording matches '{}'", selector));
+            }
+            for i in matches {
+                if !selected.contains(&i) {
+                    selected.push(i);
+                }
+            }
         }
+        Ok(selected)
     }
 
-    pub fn write_file(target: WriteTarget, items: &Vec<Uhms>) {
-        let formatted = match serde_json::to_string_pretty(items) {
-            Ok(formatted) => formatted,
-            Err(e) => cli_exit!("Cannot format items: {}", e),
+    /// Whether the metadata of a recording matches all `KEY=VALUE` filters. An empty value
+    /// matches recordings without a value. The key `name` matches the name of a recording.
+    pub fn matches_meta(uhm: &Uhms, filters: &[String]) -> Result<bool, String> {
+        for filter in filters {
+            let Some((key, value)) = filter.split_once('=') else {
+                return Err(format!("invalid filter '{}', expected KEY=VALUE", filter));
+            };
+            let matches = match key.trim() {
+                "name" => uhm.name.as_deref().unwrap_or_default() == value.trim(),
+                key => uhm.meta.matches(key, value.trim())?,
+            };
+            if !matches {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Read the recordings selected by `selectors` which match all `filters`, see [select]
+    /// and [matches_meta], and started in `[since, before)`, each with the name of the store
+    /// it was read from. Databases only read matching recordings if there are no selectors,
+    /// which refer to positions in all files.
+    pub fn read_selected(
+        store: &MultiStore,
+        selectors: &[String],
+        filters: &[String],
+        since: Option<DateTime<Utc>>,
+        before: Option<DateTime<Utc>>,
+    ) -> Result<Vec<(String, Uhms)>, String> {
+        let mut query = Query {
+            since,
+            before,
+            ..Default::default()
         };
+        for (key, value) in filters.iter().filter_map(|f| f.split_once('=')) {
+            match (key.trim(), value.trim()) {
+                (_, "") => {}
+                ("name", name) => query.name = Some(name.to_string()),
+                ("tag" | "tags", tag) => query.tags.push(tag.to_string()),
+                _ => {}
+            }
+        }
 
-        match target.write(&formatted) {
-            Ok(_) => {}
-            Err(e) => cli_exit!("Cannot write to {}: {}", target.map("stdout", |f| f), e),
+        let recordings = if selectors.is_empty() {
+            store.query_with_sources(&query)?
+        } else {
+            store.list_with_sources()?
+        };
+        let (sources, data): (Vec<String>, Vec<Uhms>) = recordings.into_iter().unzip();
+        let mut selected = Vec::new();
+        for i in select_indices(&data, selectors)? {
+            if matches_meta(&data[i], filters)? && query.matches(&data[i]) {
+                selected.push((sources[i].clone(), data[i].clone()));
+            }
         }
+        Ok(selected)
+    }
+
+    /// A short description of a recording, consisting of its name, start time and ID.
+    pub fn describe(uhm: &Uhms) -> String {
+        let start = uhm.start.format("%Y-%m-%d %H:%M:%S");
+        match &uhm.name {
+            Some(name) => format!("{} ({}, {})", name, start, uhm.id),
+            None => format!("{} ({})", start, uhm.id),
+        }
+    }
+
+    /// Where to write changes given with `-o`: `-` is stdout and no path is the source.
+    pub fn output_target(to: Option<&str>, source: &ReadSource) -> WriteTarget {
+        match to {
+            Some("-") => WriteTarget::Stdout,
+            Some(to) => WriteTarget::File(to.to_string()),
+            None => source.clone().into(),
+        }
+    }
+
+    /// Lock a data file for reading, changing and writing it, so concurrent uhm processes
+    /// do not overwrite each other's changes. Waits until the file is unlocked.
+    pub fn lock_file(target: &WriteTarget) -> Option<FileLock> {
+        let WriteTarget::File(path) = target else {
+            return None;
+        };
+        let lock = match FileLock::try_acquire(path) {
+            Ok(Some(lock)) => Ok(lock),
+            Ok(None) => {
+                println!(
+                    "Waiting for another uhm process to finish writing {}...",
+                    path
+                );
+                FileLock::acquire(path)
+            }
+            Err(e) => Err(e),
+        };
+        match lock {
+            Ok(lock) => Some(lock),
+            Err(e) => cli_exit!("Cannot lock {}: {}", path, e),
+        }
+    }
+
+    /// The data files given with `-f`, with directories and patterns expanded, see
+    /// [crate::io::data_files].
+    pub fn data_files(paths: &[String]) -> Vec<String> {
+        let mut files = Vec::new();
+        for path in paths {
+            let expanded = match crate::io::data_files(path) {
+                Ok(expanded) => expanded,
+                Err(e) => cli_exit!("Cannot read from {}: {}", path, e),
+            };
+            if expanded.is_empty() {
+                cli_exit!("No data files found in {}", path);
+            }
+            for file in expanded {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        files
+    }
+
+    /// How a data file is stored.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+    pub enum Storage {
+        /// A single JSON document.
+        Json,
+        /// JSON Lines, which new recordings are appended to.
+        Jsonl,
+        /// An SQLite database. Requires the `sqlite` feature.
+        Sqlite,
+    }
+
+    impl From<Format> for Storage {
+        fn from(format: Format) -> Self {
+            match format {
+                Format::Json => Self::Json,
+                Format::Lines => Self::Jsonl,
+            }
+        }
+    }
+
+    /// The first bytes of every SQLite database file.
+    const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+    /// How a data file is stored. Existing files keep their storage, new files are
+    /// detected by their extension, see [Format::from_path].
+    pub fn detect_storage(path: &str) -> Storage {
+        let mut header = [0; SQLITE_HEADER.len()];
+        let is_database = std::fs::File::open(path)
+            .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut header))
+            .is_ok_and(|_| header == SQLITE_HEADER);
+        if is_database {
+            return Storage::Sqlite;
+        }
+        if let Some(line) = first_line(path) {
+            return Format::detect(&line).into();
+        }
+
+        match std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+        {
+            Some("db" | "sqlite" | "sqlite3") => Storage::Sqlite,
+            _ => Format::from_path(path).into(),
+        }
+    }
+
+    /// Read the first line of a file, if it exists and is not empty.
+    fn first_line(path: &str) -> Option<String> {
+        let file = std::fs::File::open(path).ok()?;
+        let mut line = String::new();
+        std::io::BufRead::read_line(&mut std::io::BufReader::new(file), &mut line).ok()?;
+        (!line.trim().is_empty()).then_some(line)
+    }
+
+    fn print_meta<W: std::io::Write>(
+        meta: &crate::Metadata,
+        writer: &mut W,
+    ) -> Result<(), std::io::Error> {
+        let fields = [
+            ("Speaker", meta.speaker.clone()),
+            ("Title", meta.title.clone()),
+            ("Event", meta.event.clone()),
+            ("Language", meta.language.clone()),
+            (
+                "Tags",
+                (!meta.tags.is_empty()).then(|| meta.tags.join(", ")),
+            ),
+            ("Audience", meta.audience.map(|a| a.to_string())),
+            ("Rehearsal", meta.rehearsal.then(|| "yes".to_string())),
+        ];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                writeln!(writer, " > {:<9} {}", label, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn print_groups<W: std::io::Write>(
+        key: &str,
+        groups: &[crate::stats::GroupStats],
+        writer: &mut W,
+    ) -> Result<(), std::io::Error> {
+        writeln!(writer, "Grouped by {}", key)?;
+        for group in groups {
+            writeln!(
+                writer,
+                " > {:<12} {} recordings, {} uhm in {}:{:02.0} min, {:.2} uhm/min",
+                group.name.as_deref().unwrap_or("(none)"),
+                group.recordings,
+                group.count,
+                group.min_sec.0,
+                group.min_sec.1,
+                group.per_minute
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn print_agreement<W: std::io::Write>(
+        reference: &Uhms,
+        other: &Uhms,
+        agreement: &crate::merge::Agreement,
+        writer: &mut W,
+    ) -> Result<(), std::io::Error> {
+        writeln!(writer, "{} vs {}", describe(reference), describe(other))?;
+        writeln!(writer, " > Matched   {} uhm", agreement.matched)?;
+        writeln!(writer, " > Missed    {} uhm", agreement.missed)?;
+        writeln!(writer, " > Extra     {} uhm", agreement.extra)?;
+        writeln!(writer, " > Kappa     {:.2}", agreement.kappa)?;
+        Ok(())
     }
 
     pub fn print_stats<W: std::io::Write>(
         uhm: &Uhms,
         json: bool,
         writer: &mut W,
+    ) -> Result<(), std::io::Error> {
+        print_stats_from(uhm, None, json, writer)
+    }
+
+    /// Like [print_stats], but also shows the data file the recording was read from.
+    pub fn print_stats_from<W: std::io::Write>(
+        uhm: &Uhms,
+        source: Option<&str>,
+        json: bool,
+        writer: &mut W,
     ) -> Result<(), std::io::Error> {
         let stats = uhm.stats();
         if json {
-            match serde_json::to_writer(std::io::stdout(), &stats) {
+            #[derive(serde::Serialize)]
+            struct Sourced<'a, T> {
+                #[serde(skip_serializing_if = "Option::is_none")]
+                file: Option<&'a str>,
+                #[serde(flatten)]
+                stats: T,
+            }
+
+            match serde_json::to_writer(
+                writer,
+                &Sourced {
+                    file: source,
+                    stats,
+                },
+            ) {
                 Ok(()) => Ok(()),
                 Err(e) => crate::cli_exit!("Cannot format json: {}", e),
             }
@@ -103,16 +627,90 @@ mod utils {
                 writeln!(writer, "{}", uhm.start.format("%Y-%m-%d at %H:%M:%S"))?;
             }
 
+            writeln!(writer, " > ID        {}", uhm.id)?;
+            if let Some(source) = source {
+                writeln!(writer, " > File      {}", source)?;
+            }
+            print_meta(&uhm.meta, writer)?;
             writeln!(writer, " > Count     {} uhm", stats.count)?;
             writeln!(
                 writer,
                 " > Duration  {}:{:02.0} min",
                 stats.min_sec.0, stats.min_sec.1
             )?;
+            if !uhm.pauses.is_empty() {
+                writeln!(
+                    writer,
+                    " > Paused    {}:{:02.0} min ({}x)",
+                    stats.paused_min_sec.0,
+                    stats.paused_min_sec.1,
+                    uhm.pauses.len()
+                )?;
+            }
+            if let (Some((min, sec)), Some(overtime)) = (stats.planned_min_sec, stats.overtime) {
+                let (over_min, over_sec) = crate::stats::min_sec(&chrono::TimeDelta::milliseconds(
+                    (overtime.abs() * 1000.) as i64,
+                ));
+                writeln!(
+                    writer,
+                    " > Planned   {}:{:02.0} min ({}:{:02.0} min {})",
+                    min,
+                    sec,
+                    over_min,
+                    over_sec,
+                    if overtime > 0. { "over" } else { "under" }
+                )?;
+            }
             writeln!(writer, " > Mean      {:.2} s", stats.delay_mean / 1000.)?;
             writeln!(writer, " > Deviation {:.2} s", stats.delay_std / 1000.)?;
             writeln!(writer, " > Score     {:.2} uhm/min", stats.per_minute)?;
 
+            if !stats.categories.is_empty() {
+                writeln!(writer, " > Categories")?;
+                for category in &stats.categories {
+                    writeln!(
+                        writer,
+                        "   - {:<10} {} uhm, {:.2} uhm/min",
+                        category.name.as_deref().unwrap_or("(none)"),
+                        category.count,
+                        category.per_minute
+                    )?;
+                }
+            }
+
+            if !stats.sections.is_empty() {
+                writeln!(writer, " > Sections")?;
+                for (i, section) in stats.sections.iter().enumerate() {
+                    let (min, sec) =
+                        crate::stats::min_sec(&chrono::TimeDelta::milliseconds(section.start));
+                    writeln!(
+                        writer,
+                        "   {:>2}. {:<16} from {}:{:02.0}, {} uhm, {:.2} uhm/min",
+                        i + 1,
+                        section.label.as_deref().unwrap_or("-"),
+                        min,
+                        sec,
+                        section.count,
+                        section.per_minute
+                    )?;
+                }
+            }
+
+            if !stats.speakers.is_empty() {
+                writeln!(writer, " > Speakers")?;
+                for speaker in &stats.speakers {
+                    writeln!(
+                        writer,
+                        "   - {:<10} {} uhm in {}:{:02.0} min, {:.2} uhm/min",
+                        speaker.name.as_deref().unwrap_or("(none)"),
+                        speaker.count,
+                        speaker.min_sec.0,
+                        speaker.min_sec.1,
+                        speaker.per_minute
+                    )?;
+                }
+            }
+
             Ok(())
         }
     }
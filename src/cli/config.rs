@@ -0,0 +1,26 @@
+use crate::config::Config;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Print the effective configuration, including the default key bindings.
+    Show,
+}
+
+pub fn run(config: &Config, args: Args) {
+    match args.command {
+        Command::Show => {
+            match (&config.path, Config::default_path()) {
+                (Some(path), _) => println!("# Read from {}", path),
+                (None, Some(path)) => println!("# Defaults, {} does not exist", path),
+                (None, None) => println!("# Defaults"),
+            }
+            print!("{}", config.to_toml());
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::utils::Storage;
+use crate::{
+    cli_exit,
+    io::{ReadSource, WriteTarget},
+    schema::Format,
+    validate::{self, Problem},
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The storage to convert the data file into.
+    #[arg(value_enum)]
+    pub format: Storage,
+    /// Where to write the converted file. Defaults to the input file, or stdout for stdin.
+    #[clap(short = 'o', long = "output-file")]
+    pub to: Option<String>,
+}
+
+pub fn run(source: ReadSource, storage: Option<Storage>, args: Args) {
+    let target = match args.to {
+        Some(to) => WriteTarget::File(to),
+        None => source.clone().into(),
+    };
+    let _lock = super::utils::lock_file(&target);
+    let data = match super::utils::open_source(&source, storage).and_then(|store| store.list()) {
+        Ok(data) => data,
+        Err(e) => cli_exit!("{}", e),
+    };
+
+    let WriteTarget::File(to) = &target else {
+        let formatted = match args.format {
+            Storage::Json => Format::Json.to_string(&data),
+            Storage::Jsonl => Format::Lines.to_string(&data),
+            Storage::Sqlite => cli_exit!("Cannot write a database to stdout"),
+        };
+        match formatted.map(|formatted| target.write(&formatted)) {
+            Ok(Ok(_)) => return,
+            Ok(Err(e)) => cli_exit!("Cannot write to stdout: {}", e),
+            Err(e) => cli_exit!("Cannot format items: {}", e),
+        }
+    };
+
+    // databases reject duplicate IDs, so they are checked before the file is created
+    let duplicate = validate::validate_all(&data)
+        .into_iter()
+        .find_map(|d| match d.problem {
+            Problem::DuplicateId { id } => Some(id),
+            _ => None,
+        });
+    if let (Storage::Sqlite, Some(id)) = (args.format, duplicate) {
+        cli_exit!(
+            "ID {} is used more than once, run `uhm validate --fix` to assign new IDs",
+            id
+        );
+    }
+
+    // a file of another storage cannot be opened as the new one, so it is moved to a backup
+    let exists = std::path::Path::new(to).is_file();
+    if exists && super::utils::detect_storage(to) != args.format {
+        let moved = crate::io::rotate_backups(to, super::utils::BACKUPS)
+            .and_then(|_| std::fs::remove_file(to));
+        if let Err(e) = moved {
+            cli_exit!("Cannot write to {}: {}", to, e);
+        }
+    }
+
+    let count = data.len();
+    let converted =
+        super::utils::open_store_as(to, args.format).and_then(|mut store| store.replace(data));
+    if let Err(e) = converted {
+        cli_exit!("Cannot write to {}: {}", to, e);
+    }
+    println!("Converted {} recordings into {}", count, to);
+}
@@ -0,0 +1,124 @@
+use super::utils::Storage;
+use crate::{Metadata, Uhms, cli_exit, io::ReadSource};
+
+// Metadata flags shared by all commands which create or change recordings. This is a plain
+// comment because clap would show a doc comment as the about of every flattening command.
+#[derive(clap::Args, Debug, Default, Clone)]
+#[group(skip)]
+pub struct MetadataArgs {
+    /// The person giving the presentation, stored as the `speaker` key.
+    #[arg(long = "presenter", value_name = "NAME")]
+    pub speaker: Option<String>,
+    /// The event or venue of the presentation.
+    #[arg(long = "event")]
+    pub event: Option<String>,
+    /// The title of the talk.
+    #[arg(long = "title")]
+    pub title: Option<String>,
+    /// The language of the talk, e.g. `en`.
+    #[arg(long = "language")]
+    pub language: Option<String>,
+    /// Add a tag. Can be given several times.
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+    /// The number of people in the audience.
+    #[arg(long = "audience", value_name = "SIZE")]
+    pub audience: Option<u32>,
+    /// Mark the presentation as a rehearsal.
+    #[arg(long = "rehearsal", action = clap::ArgAction::SetTrue)]
+    pub rehearsal: bool,
+}
+
+impl MetadataArgs {
+    /// Set all given metadata. Empty strings unset a key.
+    pub fn apply(&self, meta: &mut Metadata) {
+        let set = |field: &mut Option<String>, value: &Option<String>| {
+            if let Some(value) = value {
+                *field = (!value.is_empty()).then(|| value.clone());
+            }
+        };
+        set(&mut meta.speaker, &self.speaker);
+        set(&mut meta.event, &self.event);
+        set(&mut meta.title, &self.title);
+        set(&mut meta.language, &self.language);
+        for tag in &self.tags {
+            if !meta.tags.contains(tag) {
+                meta.tags.push(tag.clone());
+            }
+        }
+        if self.audience.is_some() {
+            meta.audience = self.audience;
+        }
+        if self.rehearsal {
+            meta.rehearsal = true;
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The recordings to change, selected by ID, ID prefix, index or name.
+    #[arg(required = true)]
+    pub names: Vec<String>,
+    /// Rename the recordings.
+    #[arg(short = 'n', long = "name")]
+    pub name: Option<String>,
+    /// Replace the notes of the recordings.
+    #[arg(short = 'm', long = "message")]
+    pub notes: Option<String>,
+    #[command(flatten)]
+    pub meta: MetadataArgs,
+    /// Remove a tag. Can be given several times.
+    #[arg(long = "untag", value_name = "TAG")]
+    pub untag: Vec<String>,
+    /// Unmark the presentation as a rehearsal.
+    #[arg(long = "no-rehearsal", action = clap::ArgAction::SetTrue, conflicts_with = "rehearsal")]
+    pub no_rehearsal: bool,
+    /// Where to write the changed file, `-` for stdout. Defaults to the input file, or stdout
+    /// for stdin.
+    #[clap(short = 'o', long = "output-file")]
+    pub to: Option<String>,
+}
+
+pub fn run(source: ReadSource, storage: Option<Storage>, args: Args) {
+    let target = super::utils::output_target(args.to.as_deref(), &source);
+    let _lock = super::utils::lock_file(&target);
+    let mut store = match super::utils::open_changes(&source, &target, storage) {
+        Ok(store) => store,
+        Err(e) => cli_exit!("{}", e),
+    };
+    let data = match store.list() {
+        Ok(data) => data,
+        Err(e) => cli_exit!("{}", e),
+    };
+
+    let selected: Vec<Uhms> = match super::utils::select(&data, &args.names) {
+        Ok(selected) => selected.into_iter().cloned().collect(),
+        Err(e) => cli_exit!("{}", e),
+    };
+
+    let changed = selected.len();
+    for mut uhm in selected {
+        if let Some(name) = &args.name {
+            uhm.name = (!name.is_empty()).then(|| name.clone());
+        }
+        if let Some(notes) = &args.notes {
+            uhm.notes = (!notes.is_empty()).then(|| notes.clone());
+        }
+        args.meta.apply(&mut uhm.meta);
+        uhm.meta.tags.retain(|tag| !args.untag.contains(tag));
+        if args.no_rehearsal {
+            uhm.meta.rehearsal = false;
+        }
+        if let Err(e) = store.update(uhm) {
+            cli_exit!("Cannot write to {}: {}", target.map("stdout", |f| f), e);
+        }
+    }
+
+    if let Err(e) = super::utils::save(&source, &target, storage, store.as_ref()) {
+        cli_exit!("Cannot write to {}: {}", target.map("stdout", |f| f), e);
+    }
+    if target.is_file() {
+        println!("Changed {} recordings", changed);
+    }
+}
@@ -0,0 +1,85 @@
+use super::utils::Storage;
+use crate::{
+    cli_exit,
+    config::Config,
+    import::{self, Format},
+    io::{ReadSource, WriteTarget},
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The file to import: plain text, CSV or SRT/WebVTT subtitles.
+    pub path: String,
+    /// The format of the file. Guessed from its extension by default.
+    #[arg(short = 'F', long = "format", value_enum)]
+    pub format: Option<Format>,
+    /// The start of the talk, e.g. `2025-01-01 10:00:00` (UTC) or `2025-01-01T10:00:00+01:00`.
+    /// Relative timestamps are relative to it.
+    #[arg(long = "start", value_parser = parse_start)]
+    pub start: Option<chrono::DateTime<chrono::Utc>>,
+    /// The duration of the talk, e.g. `5m` or `20:00`. Defaults to the last timestamp.
+    #[arg(short = 'd', long = "duration", value_parser = super::record::parse_duration)]
+    pub duration: Option<chrono::TimeDelta>,
+    /// A filler phrase to search for in subtitles, optionally with its category. Replaces
+    /// the configured lexicon if given.
+    #[arg(short = 'w', long = "filler", value_name = "PHRASE[=CATEGORY]")]
+    pub fillers: Vec<String>,
+    #[arg(short = 'n', long = "name")]
+    pub name: Option<String>,
+    #[arg(short = 'm', long = "message")]
+    pub notes: Option<String>,
+    #[clap(short = 'o', long = "output-file", default_value = "uhm.json")]
+    pub to: String,
+    #[arg(short = 'j', long = "json", action = clap::ArgAction::SetTrue)]
+    pub json: bool,
+    #[clap(short = 's', long = "no-stats", action = clap::ArgAction::SetFalse)]
+    pub print_stats: bool,
+}
+
+pub(super) fn parse_start(s: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    match import::parse_time(s) {
+        Some(import::Time::Absolute(time)) => Ok(time),
+        _ => Err(format!(
+            "invalid start '{}', expected e.g. 2025-01-01 10:00:00",
+            s
+        )),
+    }
+}
+
+pub fn run(source: ReadSource, storage: Option<Storage>, config: &Config, args: Args) {
+    let content = match std::fs::read_to_string(&args.path) {
+        Ok(content) => content,
+        Err(e) => cli_exit!("Cannot read {}: {}", args.path, e),
+    };
+
+    let format = args.format.unwrap_or_else(|| Format::detect(&args.path));
+    let lexicon = super::utils::lexicon(config, &args.fillers);
+    let stamps = match import::parse(&content, format, &lexicon) {
+        Ok(stamps) => stamps,
+        Err(e) => cli_exit!("Cannot import {}: {}", args.path, e),
+    };
+    let mut new = match import::to_uhms(&stamps, args.start, args.duration) {
+        Ok(uhm) => uhm,
+        Err(e) => cli_exit!("Cannot import {}: {}", args.path, e),
+    };
+    new.name = args.name;
+    new.notes = args.notes;
+
+    let target = WriteTarget::File(args.to);
+    let _lock = super::utils::lock_file(&target);
+    let mut store = match super::utils::open_changes(&source, &target, storage) {
+        Ok(store) => store,
+        Err(e) => cli_exit!("{}", e),
+    };
+
+    println!("Imported {} uhm from {}", new.data.len(), args.path);
+    match super::utils::add(&source, &target, storage, store.as_mut(), new.clone()) {
+        Ok(id) => new.id = id,
+        Err(e) => cli_exit!("Cannot write to {}: {}", target.map("stdout", |f| f), e),
+    }
+
+    if args.print_stats {
+        let mut writer = std::io::stdout();
+        let _ = super::utils::print_stats(&new, args.json, &mut writer);
+    }
+}
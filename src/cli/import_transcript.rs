@@ -0,0 +1,77 @@
+use super::utils::Storage;
+use crate::{
+    cli_exit,
+    config::Config,
+    io::{ReadSource, WriteTarget},
+    transcript::{self, Format},
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The transcript to scan: SRT/WebVTT subtitles or a JSON word list.
+    pub path: String,
+    /// The format of the transcript. Guessed from its extension by default.
+    #[arg(short = 'F', long = "format", value_enum)]
+    pub format: Option<Format>,
+    /// The start of the talk, e.g. `2025-01-01 10:00:00` (UTC). Defaults to now.
+    #[arg(long = "start", value_parser = super::import_timestamps::parse_start)]
+    pub start: Option<chrono::DateTime<chrono::Utc>>,
+    /// A filler phrase to search for, optionally with its category, e.g. `um=uhm` or
+    /// `you know`. Replaces the configured lexicon if given.
+    #[arg(short = 'w', long = "filler", value_name = "PHRASE[=CATEGORY]")]
+    pub fillers: Vec<String>,
+    #[arg(short = 'n', long = "name")]
+    pub name: Option<String>,
+    #[arg(short = 'm', long = "message")]
+    pub notes: Option<String>,
+    #[clap(short = 'o', long = "output-file", default_value = "uhm.json")]
+    pub to: String,
+    #[arg(short = 'j', long = "json", action = clap::ArgAction::SetTrue)]
+    pub json: bool,
+    #[clap(short = 's', long = "no-stats", action = clap::ArgAction::SetFalse)]
+    pub print_stats: bool,
+}
+
+pub fn run(source: ReadSource, storage: Option<Storage>, config: &Config, args: Args) {
+    let content = match std::fs::read_to_string(&args.path) {
+        Ok(content) => content,
+        Err(e) => cli_exit!("Cannot read {}: {}", args.path, e),
+    };
+
+    let format = args.format.unwrap_or_else(|| Format::detect(&args.path));
+    let words = match transcript::parse(&content, format) {
+        Ok(words) => words,
+        Err(e) => cli_exit!("Cannot parse transcript {}: {}", args.path, e),
+    };
+    let lexicon = super::utils::lexicon(config, &args.fillers);
+    if lexicon.is_empty() {
+        cli_exit!("No filler phrases to search for");
+    }
+
+    let mut new = transcript::to_uhms(&words, &lexicon, args.start);
+    new.name = args.name;
+    new.notes = args.notes;
+
+    let target = WriteTarget::File(args.to);
+    let _lock = super::utils::lock_file(&target);
+    let mut store = match super::utils::open_changes(&source, &target, storage) {
+        Ok(store) => store,
+        Err(e) => cli_exit!("{}", e),
+    };
+
+    println!(
+        "Found {} fillers in {} words of {}",
+        new.data.len(),
+        words.len(),
+        args.path
+    );
+    match super::utils::add(&source, &target, storage, store.as_mut(), new.clone()) {
+        Ok(id) => new.id = id,
+        Err(e) => cli_exit!("Cannot write to {}: {}", target.map("stdout", |f| f), e),
+    }
+
+    if args.print_stats {
+        let mut writer = std::io::stdout();
+        let _ = super::utils::print_stats(&new, args.json, &mut writer);
+    }
+}
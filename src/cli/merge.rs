@@ -0,0 +1,85 @@
+use super::utils::Storage;
+use crate::{cli_exit, io::ReadSource};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The recordings to merge, by ID, ID prefix, index or name. All recordings with one of
+    /// the given names are merged.
+    #[arg(required = true)]
+    pub names: Vec<String>,
+    /// Maximum distance in seconds between uhms of different recordings to count as one.
+    #[arg(short = 't', long = "tolerance", default_value_t = 1.0, value_parser = parse_tolerance)]
+    pub tolerance: f64,
+    /// Minimum number of recordings which must contain a uhm. Defaults to a majority.
+    #[arg(short = 'q', long = "quorum")]
+    pub quorum: Option<usize>,
+    /// Name of the merged recording. Defaults to the name of the first merged recording.
+    #[arg(short = 'n', long = "name")]
+    pub name: Option<String>,
+    #[arg(short = 'm', long = "message")]
+    pub notes: Option<String>,
+    /// Where to write the merged recording, `-` for stdout.
+    #[clap(short = 'o', long = "output-file", default_value = "uhm.json")]
+    pub to: String,
+    #[arg(short = 'j', long = "json", action = clap::ArgAction::SetTrue)]
+    pub json: bool,
+    #[clap(short = 's', long = "no-stats", action = clap::ArgAction::SetFalse)]
+    pub print_stats: bool,
+}
+
+pub fn run(source: ReadSource, storage: Option<Storage>, args: Args) {
+    let target = super::utils::output_target(Some(&args.to), &source);
+    let _lock = super::utils::lock_file(&target);
+    let mut store = match super::utils::open_changes(&source, &target, storage) {
+        Ok(store) => store,
+        Err(e) => cli_exit!("{}", e),
+    };
+    let data = match store.list() {
+        Ok(data) => data,
+        Err(e) => cli_exit!("{}", e),
+    };
+
+    let selected = match super::utils::select(&data, &args.names) {
+        Ok(selected) => selected,
+        Err(e) => cli_exit!("{}", e),
+    };
+    if selected.len() < 2 {
+        cli_exit!(
+            "Need at least two recordings to merge, found {}",
+            selected.len()
+        );
+    }
+
+    let quorum = args.quorum.unwrap_or(selected.len() / 2 + 1);
+    let tolerance = chrono::TimeDelta::milliseconds((args.tolerance * 1000.) as i64);
+    let mut merged = crate::merge::merge(&selected, tolerance, quorum);
+    if args.name.is_some() {
+        merged.name = args.name;
+    }
+    if args.notes.is_some() {
+        merged.notes = args.notes;
+    }
+
+    match super::utils::add(&source, &target, storage, store.as_mut(), merged.clone()) {
+        Ok(id) => merged.id = id,
+        Err(e) => cli_exit!("Cannot write to {}: {}", target.map("stdout", |f| f), e),
+    }
+
+    // keep the merged data on stdout parseable
+    if args.print_stats && target.is_stdout() {
+        let _ = super::utils::print_stats(&merged, args.json, &mut std::io::stderr());
+    } else if args.print_stats {
+        let _ = super::utils::print_stats(&merged, args.json, &mut std::io::stdout());
+    }
+}
+
+/// Parse a tolerance in seconds, which must be a finite number of at least zero.
+pub(super) fn parse_tolerance(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds >= 0. => Ok(seconds),
+        _ => Err(format!(
+            "invalid tolerance '{}', expected a number of seconds of at least 0",
+            s
+        )),
+    }
+}
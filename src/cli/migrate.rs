@@ -0,0 +1,48 @@
+use super::utils::Storage;
+use crate::{
+    cli_exit,
+    io::{ReadSource, WriteTarget},
+    schema::VERSION,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Where to write the migrated file. Defaults to the input file, or stdout for stdin.
+    #[clap(short = 'o', long = "output-file")]
+    pub to: Option<String>,
+}
+
+pub fn run(source: ReadSource, storage: Option<Storage>, args: Args) {
+    let target = match args.to {
+        Some(to) => WriteTarget::File(to),
+        None => source.clone().into(),
+    };
+    let _lock = super::utils::lock_file(&target);
+    let mut store = match super::utils::open_changes(&source, &target, storage) {
+        Ok(store) => store,
+        Err(e) => cli_exit!("{}", e),
+    };
+    let (recordings, version) = match store.list().and_then(|data| Ok((data, store.version()?))) {
+        Ok(read) => read,
+        Err(e) => cli_exit!("{}", e),
+    };
+
+    if version == VERSION && target.map(false, |to| source.map(false, |from| from == to)) {
+        println!("Already at schema version {}", VERSION);
+        return;
+    }
+
+    let count = recordings.len();
+    let migrated = store
+        .replace(recordings)
+        .and_then(|_| super::utils::save(&source, &target, storage, store.as_ref()));
+    if let Err(e) = migrated {
+        cli_exit!("Cannot write to {}: {}", target.map("stdout", |f| f), e);
+    }
+    if target.is_file() {
+        println!(
+            "Migrated {} recordings from schema version {} to {}",
+            count, version, VERSION
+        );
+    }
+}
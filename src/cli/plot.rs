@@ -1,16 +1,31 @@
+use super::utils::Storage;
 use crate::{
+    Uhms,
     io::{ReadSource, WriteTarget},
     plot::Artist,
 };
 
 #[derive(clap::Args, Debug, Clone)]
 pub struct Args {
+    /// The recordings to plot, selected by ID, ID prefix, index or name. Defaults to all.
     #[arg()]
-    name: Option<String>,
+    names: Vec<String>,
     #[arg(short = 'o')]
     outfile: Option<String>,
     #[arg(name = "FORMAT", long = "format", default_value = "tikz")]
     artist: ArtistChoice,
+    /// Only plot recordings whose metadata or name matches, e.g. `speaker=Ann` or `tag=demo`.
+    #[arg(short = 'w', long = "where", value_name = "KEY=VALUE")]
+    filters: Vec<String>,
+    /// Only include recordings which started at or after this time, e.g. `2025-01-01 00:00:00`.
+    #[arg(long = "since", value_parser = super::import_timestamps::parse_start)]
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only include recordings which started before this time.
+    #[arg(long = "before", value_parser = super::import_timestamps::parse_start)]
+    before: Option<chrono::DateTime<chrono::Utc>>,
+    /// Order the recordings by a metadata value, e.g. `speaker`, and label them with it.
+    #[arg(short = 'g', long = "group-by", value_name = "KEY")]
+    group_by: Option<String>,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -25,9 +40,22 @@ pub enum ArtistChoice {
     CeTZSmall,
 }
 
-pub fn run(source: ReadSource, args: Args) {
-    let data = match super::utils::read_file(&source) {
-        Ok(uhms) => uhms,
+pub fn run(sources: &[ReadSource], storage: Option<Storage>, args: Args) {
+    let store = match super::utils::open_sources(sources, storage) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let selected = match super::utils::read_selected(
+        &store,
+        &args.names,
+        &args.filters,
+        args.since,
+        args.before,
+    ) {
+        Ok(selected) => selected,
         Err(e) => {
             eprintln!("{}", e);
             std::process::exit(1);
@@ -39,29 +67,43 @@ pub fn run(source: ReadSource, args: Args) {
         Some(fname) => WriteTarget::File(fname.to_string()),
     };
 
-    let filtered = if let Some(name) = args.name {
-        let mut result = Vec::with_capacity(data.len() / 2);
-
-        for uhm in data {
-            if let Some(n) = &uhm.name {
-                if n == &name {
-                    result.push(uhm);
-                }
+    let labelled = store.len() > 1;
+    let data: Vec<Uhms> = selected
+        .into_iter()
+        .map(|(source, mut uhm)| {
+            if labelled {
+                label_source(&source, &mut uhm);
             }
-        }
-
-        result
-    } else {
-        data
-    };
+            uhm
+        })
+        .collect();
+    let filtered: Vec<&Uhms> = data.iter().collect();
 
-    if filtered.len() == 0 {
+    if filtered.is_empty() {
         println!("No entries to plot");
         return;
     }
 
+    let filtered = match &args.group_by {
+        Some(key) => match group(filtered, key) {
+            Ok(grouped) => grouped,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => filtered.into_iter().cloned().collect(),
+    };
+
     let mut plot = crate::plot::Canvas::new();
     let mut options = crate::plot::PlotOptions::default();
+    for uhm in &filtered {
+        for category in uhm.category_names() {
+            if !options.categories.contains(category) {
+                options.categories.push(category.clone());
+            }
+        }
+    }
 
     for uhm in filtered {
         crate::plot_uhm(&uhm, &mut plot, &options);
@@ -77,3 +119,41 @@ pub fn run(source: ReadSource, args: Args) {
 
     let _ = target.write(&rendered);
 }
+
+/// Prefix the name of a recording with the name of the data file it was read from.
+fn label_source(source: &str, uhm: &mut Uhms) {
+    let file = std::path::Path::new(source)
+        .file_name()
+        .map_or(source.into(), |f| f.to_string_lossy());
+    uhm.name = Some(match &uhm.name {
+        Some(name) => format!("{}: {}", file, name),
+        None => file.into_owned(),
+    });
+}
+
+/// Order recordings by the value of a metadata key and prefix their names with it.
+/// Recordings with several values, like tags, appear once per value.
+fn group(data: Vec<&Uhms>, key: &str) -> Result<Vec<Uhms>, String> {
+    let groups = crate::stats::by_group(&data, key)?;
+
+    let mut grouped = Vec::with_capacity(data.len());
+    for group in groups {
+        for uhm in &data {
+            let values = uhm.meta.values(key).unwrap_or_default();
+            let member = match &group.name {
+                Some(name) => values.contains(name),
+                None => values.is_empty(),
+            };
+            if member {
+                let mut uhm = (*uhm).clone();
+                let label = group.name.as_deref().unwrap_or("(none)");
+                uhm.name = Some(match &uhm.name {
+                    Some(name) => format!("{}: {}", label, name),
+                    None => label.to_string(),
+                });
+                grouped.push(uhm);
+            }
+        }
+    }
+    Ok(grouped)
+}
@@ -1,10 +1,35 @@
+use std::io::{BufRead, BufReader};
+
+use super::utils::Storage;
 use crate::{
-    cli_exit,
+    Precision, Uhms, cli_exit,
+    config::{Config, action_name},
     io::{ReadSource, WriteTarget},
+    record::{Journal, RecordOptions},
 };
 
-#[derive(clap::Args, Debug)]
+#[derive(clap::Args, Debug, Default)]
 pub struct Args {
+    #[command(flatten)]
+    pub session: SessionArgs,
+    /// Read actions line by line from a file or FIFO (`-` for stdin) instead of the keyboard.
+    #[arg(
+        short = 'e',
+        long = "events",
+        value_name = "PATH",
+        conflicts_with = "recover"
+    )]
+    pub events: Option<String>,
+    /// Restore an interrupted recording from its journal instead of recording a new one.
+    #[arg(long = "recover", action = clap::ArgAction::SetTrue)]
+    pub recover: bool,
+}
+
+// Flags shared by all commands which record a session. This is a plain comment because
+// clap would show a doc comment as the about of every flattening command.
+#[derive(clap::Args, Debug)]
+#[group(skip)]
+pub struct SessionArgs {
     #[arg(short = 'j', long = "json", action = clap::ArgAction::SetTrue)]
     pub json: bool,
     #[arg()]
@@ -15,9 +40,124 @@ pub struct Args {
     pub to: String,
     #[clap(short = 's', long = "no-stats", action = clap::ArgAction::SetFalse)]
     pub print_stats: bool,
+    /// Assign a category to a key, e.g. `-c u=uhm -c a=ah`.
+    #[arg(short = 'c', long = "category", value_name = "KEY=NAME", value_parser = parse_category)]
+    pub categories: Vec<(char, String)>,
+    /// Add a speaker of a panel, selected with the number keys in the given order.
+    #[arg(short = 'p', long = "speaker", value_name = "NAME")]
+    pub speakers: Vec<String>,
+    /// The planned duration of the talk, e.g. `5m`, `90s`, `1h30m` or `20:00`. The recording
+    /// stops on its own once it is used up.
+    #[arg(short = 'd', long = "duration", value_parser = parse_duration)]
+    pub duration: Option<chrono::TimeDelta>,
+    /// Keep recording after the planned duration has passed instead of stopping.
+    #[arg(long = "overtime", action = clap::ArgAction::SetTrue, requires = "duration")]
+    pub overtime: bool,
+    /// Store the times of the uhms with a finer precision than milliseconds.
+    #[arg(long = "precision", value_enum, default_value = "ms")]
+    pub precision: Precision,
+    #[command(flatten)]
+    pub meta: super::edit::MetadataArgs,
+}
+
+/// Print the keys available while recording.
+fn print_keys(options: &RecordOptions) {
+    println!("Keys:");
+    for (key, action) in options.bindings.iter() {
+        println!("  [{}] {}", key, action_name(action));
+    }
+    let mut categories: Vec<_> = options.categories.iter().collect();
+    categories.sort();
+    for (key, category) in categories {
+        println!("  [{}] category:{}", key, category);
+    }
+    for (i, speaker) in options.speakers.iter().enumerate().take(9) {
+        println!("  [{}] speaker:{}", i + 1, speaker);
+    }
+    if options.bindings.count_other_keys {
+        println!("Any other character counts a uhm.");
+    }
+}
+
+/// Ask for a label for every marker of a fresh recording. Empty input leaves a marker
+/// without a label.
+fn label_markers(uhm: &mut Uhms) {
+    let terminal = console::Term::stdout();
+    if !terminal.is_term() {
+        return;
+    }
+
+    println!("Label the sections (leave empty to skip):");
+    for (i, marker) in uhm.markers.iter_mut().enumerate() {
+        let (min, sec) = crate::stats::min_sec(&chrono::TimeDelta::milliseconds(marker.offset));
+        print!("  Section {} at {}:{:02.0}: ", i + 2, min, sec);
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+
+        match terminal.read_line() {
+            Ok(label) if !label.trim().is_empty() => marker.label = Some(label.trim().to_string()),
+            _ => {}
+        }
+    }
+}
+
+/// Parse a duration given either as `MM:SS`, `HH:MM:SS` or as a combination of hours,
+/// minutes and seconds like `1h30m` or `90s`. Plain numbers are minutes.
+pub(super) fn parse_duration(s: &str) -> Result<chrono::TimeDelta, String> {
+    let invalid = || {
+        format!(
+            "invalid duration '{}', expected e.g. 5m, 90s, 1h30m or 20:00",
+            s
+        )
+    };
+    let mut seconds: i64 = 0;
+
+    if s.contains(':') {
+        for part in s.split(':') {
+            seconds = seconds * 60 + part.parse::<i64>().map_err(|_| invalid())?;
+        }
+    } else if let Ok(minutes) = s.parse::<i64>() {
+        seconds = minutes * 60;
+    } else {
+        let mut number = String::new();
+        for c in s.chars() {
+            let unit = match c {
+                '0'..='9' => {
+                    number.push(c);
+                    continue;
+                }
+                'h' => 3600,
+                'm' => 60,
+                's' => 1,
+                _ => return Err(invalid()),
+            };
+            seconds += number.parse::<i64>().map_err(|_| invalid())? * unit;
+            number.clear();
+        }
+        if !number.is_empty() {
+            return Err(invalid());
+        }
+    }
+
+    if seconds <= 0 {
+        return Err(invalid());
+    }
+    Ok(chrono::TimeDelta::seconds(seconds))
+}
+
+fn parse_category(s: &str) -> Result<(char, String), String> {
+    let Some((key, name)) = s.split_once('=') else {
+        return Err(format!("expected KEY=NAME, got '{}'", s));
+    };
+
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(key), None) if !name.is_empty() => Ok((key, name.to_string())),
+        (Some(_), None) => Err("category name must not be empty".to_string()),
+        _ => Err(format!("key must be a single character, got '{}'", key)),
+    }
 }
 
-impl Default for Args {
+impl Default for SessionArgs {
     fn default() -> Self {
         Self {
             json: false,
@@ -25,49 +165,126 @@ impl Default for Args {
             notes: None,
             to: String::from("uhm.json"),
             print_stats: true,
+            categories: Vec::new(),
+            speakers: Vec::new(),
+            duration: None,
+            overtime: false,
+            precision: Precision::default(),
+            meta: Default::default(),
         }
     }
 }
 
-pub fn run(source: ReadSource, args: Args) {
-    if let Some(name) = &args.name {
-        println!("Recording for {}...", name);
-    } else {
-        println!("Recording...");
-    };
-    let new = crate::record(args.name, args.notes);
+pub fn run(source: ReadSource, storage: Option<Storage>, config: &Config, args: Args) {
+    if let Some(path) = args.events.clone() {
+        if path == "-" && source.is_stdin() {
+            cli_exit!("Cannot read both the data file and events from stdin");
+        }
 
-    // append to file
-    let mut data = if let ReadSource::File(f) = &source {
-        // Could not read file
-        if !std::path::Path::new(f).is_file() {
-            Vec::new()
-        } else {
-            match super::utils::read_file(&source) {
-                Ok(e) => e,
-                Err(e) => {
-                    eprintln!("{}", e);
-                    println!("{:?}", new);
-                    cli_exit!()
+        return run_with(source, storage, config, args, |name, notes, options| {
+            let input: Box<dyn BufRead + Send> = if path == "-" {
+                Box::new(BufReader::new(std::io::stdin()))
+            } else {
+                match std::fs::File::open(&path) {
+                    Ok(file) => Box::new(BufReader::new(file)),
+                    Err(e) => cli_exit!("Cannot read events from {}: {}", path, e),
                 }
+            };
+            crate::stream::record(input, name, notes, options)
+        });
+    }
+
+    run_with(source, storage, config, args, |name, notes, options| {
+        print_keys(options);
+        crate::record(name, notes, options)
+    });
+}
+
+/// Record a new session with the given front-end, or recover an interrupted one, and
+/// append it to the data file.
+pub fn run_with<F>(
+    source: ReadSource,
+    storage: Option<Storage>,
+    config: &Config,
+    args: Args,
+    record: F,
+) where
+    F: FnOnce(Option<String>, Option<String>, &RecordOptions) -> Uhms,
+{
+    let Args {
+        session: args,
+        recover,
+        ..
+    } = args;
+    let journal = Journal::path_for(&args.to);
+    let journal_exists = std::path::Path::new(&journal).is_file();
+
+    let mut new = if recover {
+        match Journal::recover(&journal) {
+            Ok(uhm) => {
+                println!("Recovered {} uhm from {}", uhm.data.len(), journal);
+                uhm
             }
+            Err(e) => cli_exit!("{}", e),
         }
     } else {
-        match super::utils::read_file(&source) {
-            Ok(e) => e,
-            Err(e) => {
-                eprintln!("{}", e);
-                println!("{:?}", new);
-                cli_exit!()
-            }
+        if journal_exists {
+            cli_exit!(
+                "Found an interrupted recording in {}. Restore it with `uhm record --recover` or delete the file.",
+                journal
+            );
+        }
+
+        if let Some(name) = &args.name {
+            println!("Recording for {}...", name);
+        } else {
+            println!("Recording...");
+        };
+        let options = RecordOptions {
+            categories: args.categories.into_iter().collect(),
+            bindings: config.bindings.clone(),
+            speakers: args.speakers,
+            duration: args.duration,
+            overtime: args.overtime,
+            journal: Some(journal.clone()),
+            precision: args.precision,
+            ..Default::default()
+        };
+        record(args.name, args.notes, &options)
+    };
+
+    args.meta.apply(&mut new.meta);
+
+    if !new.markers.is_empty() {
+        println!();
+        label_markers(&mut new);
+    }
+
+    // append to file, without losing recordings made concurrently by other processes
+    let target = WriteTarget::File(args.to.clone());
+    let _lock = super::utils::lock_file(&target);
+    let mut store = match super::utils::open_changes(&source, &target, storage) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("{}", e);
+            println!("{:?}", new);
+            cli_exit!()
         }
     };
 
     println!();
 
-    let target = WriteTarget::File(args.to);
-    data.push(new.clone());
-    super::utils::write_file(target, &data);
+    match super::utils::add(&source, &target, storage, store.as_mut(), new.clone()) {
+        Ok(id) => new.id = id,
+        Err(e) => {
+            eprintln!("Cannot write to {}: {}", args.to, e);
+            println!("{:?}", new);
+            cli_exit!()
+        }
+    }
+
+    // the recording is safe now
+    let _ = std::fs::remove_file(&journal);
 
     if args.print_stats {
         let mut writer = std::io::stdout();
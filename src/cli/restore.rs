@@ -0,0 +1,81 @@
+use super::utils::Storage;
+use crate::{
+    cli_exit,
+    io::{self, ReadSource, WriteTarget},
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The backup to restore, 1 for the newest one.
+    #[arg(
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub backup: usize,
+    /// List the available backups instead of restoring one.
+    #[arg(short = 'l', long = "list", action = clap::ArgAction::SetTrue)]
+    pub list: bool,
+}
+
+pub fn run(source: ReadSource, storage: Option<Storage>, args: Args) {
+    let ReadSource::File(path) = source else {
+        cli_exit!("Cannot restore backups of stdin");
+    };
+    let _lock = super::utils::lock_file(&WriteTarget::File(path.clone()));
+
+    let backups = io::backups(&path);
+    if args.list {
+        if backups.is_empty() {
+            println!("No backups of {}", path);
+        }
+        for (i, backup) in backups.iter().enumerate() {
+            let recordings =
+                match super::utils::open_source(&ReadSource::File(backup.clone()), storage)
+                    .and_then(|s| s.list())
+                {
+                    Ok(data) => data.len(),
+                    Err(e) => cli_exit!("{}", e),
+                };
+            let modified = std::fs::metadata(backup)
+                .and_then(|m| m.modified())
+                .map(|m| {
+                    chrono::DateTime::<chrono::Local>::from(m)
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            println!(
+                "{:>2}. {} ({} recordings, {})",
+                i + 1,
+                backup,
+                recordings,
+                modified
+            );
+        }
+        return;
+    }
+
+    let Some(backup) = backups.get(args.backup - 1) else {
+        cli_exit!(
+            "No backup {} of {}, there are {}",
+            args.backup,
+            path,
+            backups.len()
+        );
+    };
+    let data = match super::utils::open_source(&ReadSource::File(backup.clone()), storage)
+        .and_then(|s| s.list())
+    {
+        Ok(data) => data,
+        Err(e) => cli_exit!("{}", e),
+    };
+
+    // the current file becomes the newest backup, so restoring can be undone
+    let count = data.len();
+    if let Err(e) =
+        super::utils::open_store(&path, storage).and_then(|mut store| store.replace(data))
+    {
+        cli_exit!("Cannot write to {}: {}", path, e);
+    }
+    println!("Restored {} recordings from {}", count, backup);
+}
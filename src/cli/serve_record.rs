@@ -0,0 +1,32 @@
+use super::utils::Storage;
+use crate::{cli_exit, config::Config, io::ReadSource};
+
+#[derive(clap::Args, Debug)]
+#[group(skip)]
+pub struct Args {
+    /// The address to listen on. Use `0.0.0.0:PORT` to accept taps from the local network.
+    #[arg(short = 'b', long = "bind", default_value = "127.0.0.1:8765")]
+    pub bind: String,
+    #[command(flatten)]
+    pub session: super::record::SessionArgs,
+}
+
+pub fn run(source: ReadSource, storage: Option<Storage>, config: &Config, args: Args) {
+    let bind = args.bind;
+    super::record::run_with(
+        source,
+        storage,
+        config,
+        super::record::Args {
+            session: args.session,
+            ..Default::default()
+        },
+        |name, notes, options| {
+            println!("Open http://{}/ to record, stop the recording there.", bind);
+            match crate::serve::record(&bind, name, notes, options) {
+                Ok(uhm) => uhm,
+                Err(e) => cli_exit!("Cannot listen on {}: {}", bind, e),
+            }
+        },
+    );
+}
@@ -1,46 +1,129 @@
 use std::io::Write;
 
+use super::utils::Storage;
 use crate::{
-    cli_exit,
+    Uhms, cli_exit,
     io::{ReadSource, WriteTarget},
 };
 
-#[derive(clap::Args, Debug)]
+#[derive(clap::Args, Debug, Default)]
 pub struct Args {
     #[arg(short = 'j', long = "json", action = clap::ArgAction::SetTrue)]
     json: bool,
+    /// Only show these recordings, selected by ID, ID prefix, index or name.
+    #[arg()]
+    names: Vec<String>,
+    /// Compare the selected recordings of the same session with each other.
+    #[arg(short = 'a', long = "agreement", action = clap::ArgAction::SetTrue)]
+    agreement: bool,
+    /// Maximum distance in seconds between uhms of different recordings to count as one.
+    #[arg(
+        short = 't',
+        long = "tolerance",
+        default_value_t = 1.0,
+        value_parser = super::merge::parse_tolerance
+    )]
+    tolerance: f64,
+    /// Only show recordings whose metadata or name matches, e.g. `speaker=Ann` or `tag=demo`.
+    #[arg(short = 'w', long = "where", value_name = "KEY=VALUE")]
+    filters: Vec<String>,
+    /// Only include recordings which started at or after this time, e.g. `2025-01-01 00:00:00`.
+    #[arg(long = "since", value_parser = super::import_timestamps::parse_start)]
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only include recordings which started before this time.
+    #[arg(long = "before", value_parser = super::import_timestamps::parse_start)]
+    before: Option<chrono::DateTime<chrono::Utc>>,
+    /// Combine the stats of all recordings sharing a metadata value, e.g. `speaker`.
+    #[arg(
+        short = 'g',
+        long = "group-by",
+        value_name = "KEY",
+        conflicts_with = "agreement"
+    )]
+    group_by: Option<String>,
 }
 
-impl Default for Args {
-    fn default() -> Self {
-        Self { json: false }
-    }
-}
-
-pub fn run(source: ReadSource, args: Args) {
-    let data = match super::utils::read_file(&source) {
-        Ok(s) => s,
+pub fn run(sources: &[ReadSource], storage: Option<Storage>, args: Args) {
+    let store = match super::utils::open_sources(sources, storage) {
+        Ok(store) => store,
         Err(e) => cli_exit!("{}", e),
     };
+    let data = match super::utils::read_selected(
+        &store,
+        &args.names,
+        &args.filters,
+        args.since,
+        args.before,
+    ) {
+        Ok(selected) => selected,
+        Err(e) => cli_exit!("{}", e),
+    };
+    let (sources, data): (Vec<String>, Vec<Uhms>) = data.into_iter().unzip();
+    let data: Vec<&Uhms> = data.iter().collect();
     let target = WriteTarget::Stdout;
 
     let mut buf = Vec::new();
 
-    if args.json {
+    if let Some(key) = &args.group_by {
+        let groups = match crate::stats::by_group(&data, key) {
+            Ok(groups) => groups,
+            Err(e) => cli_exit!("{}", e),
+        };
+        if args.json {
+            if let Err(e) = serde_json::to_writer(&mut buf, &groups) {
+                cli_exit!("Cannot format json: {}", e);
+            }
+        } else {
+            let _ = super::utils::print_groups(key, &groups, &mut buf);
+        }
+    } else if args.agreement {
+        if data.len() < 2 {
+            cli_exit!(
+                "Need at least two recordings to compare, found {}",
+                data.len()
+            );
+        }
+
+        let tolerance = chrono::TimeDelta::milliseconds((args.tolerance * 1000.) as i64);
+        let mut results = Vec::new();
+        for (i, reference) in data.iter().enumerate() {
+            for other in &data[i + 1..] {
+                results.push((
+                    *reference,
+                    *other,
+                    crate::merge::agreement(reference, other, tolerance),
+                ));
+            }
+        }
+
+        if args.json {
+            let agreements: Vec<_> = results.iter().map(|(_, _, a)| a).collect();
+            if let Err(e) = serde_json::to_writer(&mut buf, &agreements) {
+                cli_exit!("Cannot format json: {}", e);
+            }
+        } else {
+            let _ = writeln!(buf, "Agreement within {:.2} s", args.tolerance);
+            for (reference, other, agreement) in &results {
+                let _ = super::utils::print_agreement(reference, other, agreement, &mut buf);
+            }
+        }
+    } else if args.json {
         let _ = buf.write("[\n".as_bytes());
-        for (i, uhm) in data.iter().enumerate() {
+        for (i, (uhm, source)) in data.iter().zip(&sources).enumerate() {
             let _ = buf.write("\t".as_bytes());
-            let _ = super::utils::print_stats(uhm, true, &mut buf);
+            let _ = super::utils::print_stats_from(uhm, Some(source), true, &mut buf);
 
-            if i < data.len() {
+            if i + 1 < data.len() {
                 let _ = buf.write(",".as_bytes());
             }
             let _ = buf.write("\n".as_bytes());
         }
         let _ = buf.write("]".as_bytes());
     } else {
-        for uhm in &data {
-            let _ = super::utils::print_stats(uhm, false, &mut buf);
+        for (uhm, source) in data.iter().zip(&sources) {
+            // the file is only worth showing if there are several
+            let source = (store.len() > 1).then_some(source.as_str());
+            let _ = super::utils::print_stats_from(uhm, source, false, &mut buf);
             let _ = buf.write("\n".as_bytes());
         }
     }
@@ -0,0 +1,122 @@
+use std::io::Write;
+
+use super::utils::Storage;
+use crate::{
+    Uhms, cli_exit,
+    io::ReadSource,
+    validate::{self, Diagnostic, Severity},
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Repair all problems which can be repaired without losing data.
+    #[arg(long = "fix", action = clap::ArgAction::SetTrue)]
+    pub fix: bool,
+    /// Where to write the repaired file, `-` for stdout. Defaults to the input file, or stdout
+    /// for stdin. The report is printed to stderr if the file is written to stdout.
+    #[clap(short = 'o', long = "output-file", requires = "fix")]
+    pub to: Option<String>,
+    #[arg(short = 'j', long = "json", action = clap::ArgAction::SetTrue)]
+    pub json: bool,
+}
+
+pub fn run(source: ReadSource, storage: Option<Storage>, args: Args) {
+    let target = super::utils::output_target(args.to.as_deref(), &source);
+    let _lock = args.fix.then(|| super::utils::lock_file(&target));
+    let store = match args.fix {
+        true => super::utils::open_changes(&source, &target, storage),
+        false => super::utils::open_source(&source, storage),
+    };
+    let mut store = match store {
+        Ok(store) => store,
+        Err(e) => cli_exit!("{}", e),
+    };
+    let mut data = match store.list() {
+        Ok(data) => data,
+        Err(e) => cli_exit!("{}", e),
+    };
+
+    let mut diagnostics = validate::validate_all(&data);
+    let mut fixed = 0;
+    if args.fix && diagnostics.iter().any(|d| d.fixable) {
+        fixed = validate::fix_all(&mut data);
+        let saved = store
+            .replace(data.clone())
+            .and_then(|_| super::utils::save(&source, &target, storage, store.as_ref()));
+        if let Err(e) = saved {
+            cli_exit!("Cannot write to {}: {}", target.map("stdout", |f| f), e);
+        }
+        diagnostics = validate::validate_all(&data);
+    }
+
+    // keep the repaired data on stdout parseable
+    let report = match args.fix && target.is_stdout() {
+        true => print_report(
+            &mut std::io::stderr(),
+            &data,
+            &diagnostics,
+            fixed,
+            args.json,
+        ),
+        false => print_report(
+            &mut std::io::stdout(),
+            &data,
+            &diagnostics,
+            fixed,
+            args.json,
+        ),
+    };
+    if let Err(e) = report {
+        cli_exit!("Cannot print the report: {}", e);
+    }
+
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        cli_exit!();
+    }
+}
+
+/// Print the diagnostics of all recordings in `data` and a summary.
+fn print_report(
+    writer: &mut dyn Write,
+    data: &[Uhms],
+    diagnostics: &[Diagnostic],
+    fixed: usize,
+    json: bool,
+) -> std::io::Result<()> {
+    if json {
+        let json = serde_json::to_string(diagnostics).map_err(std::io::Error::other)?;
+        return writeln!(writer, "{}", json);
+    }
+
+    for diagnostic in diagnostics {
+        let severity = match diagnostic.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        writeln!(
+            writer,
+            "#{} {}: {}: {}",
+            diagnostic.recording + 1,
+            super::utils::describe(&data[diagnostic.recording]),
+            severity,
+            diagnostic.problem
+        )?;
+    }
+
+    let fixable = diagnostics.iter().filter(|d| d.fixable).count();
+    if fixed > 0 {
+        writeln!(writer, "Fixed {} problems", fixed)?;
+    }
+    if diagnostics.is_empty() {
+        writeln!(writer, "No problems found in {} recordings", data.len())
+    } else if fixable > 0 {
+        writeln!(
+            writer,
+            "Found {} problems, {} of them can be fixed with --fix",
+            diagnostics.len(),
+            fixable
+        )
+    } else {
+        writeln!(writer, "Found {} problems", diagnostics.len())
+    }
+}
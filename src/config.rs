@@ -0,0 +1,290 @@
+//! User configuration, read from `~/.config/uhm/config.toml` by default.
+//!
+//! ```toml
+//! # Whether character keys without a binding count as a uhm without category.
+//! count_other_keys = true
+//!
+//! [keys]
+//! Enter = "stop"
+//! Tab = "pause"
+//! Backspace = "undo"
+//! Delete = "redo"
+//! ArrowRight = "marker"
+//! PageDown = "count"
+//! u = "category:uhm"
+//! a = "category:ah"
+//! ```
+//!
+//! The `[fillers]` table replaces the filler phrases searched for in transcripts, see
+//! [Lexicon]. Each phrase is mapped to the category it is counted as:
+//!
+//! ```toml
+//! [fillers]
+//! uhm = "uhm"
+//! um = "uhm"
+//! "you know" = "you know"
+//! ```
+//!
+//! Keys are either single characters or one of the names listed in [parse_key]. The
+//! available actions are `count`, `category:NAME`, `speaker:NAME`, `undo`, `redo`, `pause`,
+//! `marker`, `stop` and `none`, which removes a default binding.
+
+use std::collections::{BTreeMap, HashMap};
+
+use console::Key;
+use serde::{Deserialize, Serialize};
+
+use crate::{record::Action, transcript::Lexicon};
+
+/// Maps keys to the actions they trigger while recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings {
+    keys: HashMap<Key, Action>,
+    /// Whether character keys without a binding count as a uhm without category.
+    pub count_other_keys: bool,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let keys = [
+            (Key::Enter, Action::Stop),
+            (Key::Tab, Action::TogglePause),
+            (Key::Backspace, Action::Undo),
+            (Key::Del, Action::Redo),
+            (Key::ArrowRight, Action::Marker { label: None }),
+            (Key::PageDown, Action::Marker { label: None }),
+        ];
+        Self {
+            keys: keys.into_iter().collect(),
+            count_other_keys: true,
+        }
+    }
+}
+
+impl Bindings {
+    /// Look up the action triggered by a key.
+    pub fn action(&self, key: &Key) -> Option<Action> {
+        match (self.keys.get(key), key) {
+            (Some(action), _) => Some(action.clone()),
+            (None, Key::Char(_)) if self.count_other_keys => Some(Action::Uhm { category: None }),
+            _ => None,
+        }
+    }
+
+    /// Bind a key to an action, replacing its previous binding. `None` removes the binding.
+    pub fn bind(&mut self, key: Key, action: Option<Action>) {
+        match action {
+            Some(action) => self.keys.insert(key, action),
+            None => self.keys.remove(&key),
+        };
+    }
+
+    /// All bound keys with their actions, sorted by key name.
+    pub fn iter(&self) -> Vec<(String, &Action)> {
+        let mut bindings: Vec<_> = self.keys.iter().map(|(k, a)| (key_name(k), a)).collect();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
+
+    /// All categories which can be recorded with these bindings.
+    pub fn categories(&self) -> Vec<&String> {
+        let mut categories: Vec<&String> = self
+            .keys
+            .values()
+            .filter_map(|action| match action {
+                Action::Uhm { category } => category.as_ref(),
+                _ => None,
+            })
+            .collect();
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+}
+
+/// The contents of the configuration file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    /// The file the configuration was read from, if any.
+    pub path: Option<String>,
+    pub bindings: Bindings,
+    /// The filler phrases searched for in transcripts.
+    pub lexicon: Lexicon,
+}
+
+/// The configuration file as it is stored on disk.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ConfigFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    count_other_keys: Option<bool>,
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    fillers: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// The default location of the configuration file, `$XDG_CONFIG_HOME/uhm/config.toml`
+    /// or `~/.config/uhm/config.toml`.
+    pub fn default_path() -> Option<String> {
+        let dir = match std::env::var("XDG_CONFIG_HOME") {
+            Ok(dir) if !dir.is_empty() => dir,
+            _ => format!("{}/.config", std::env::var("HOME").ok()?),
+        };
+        Some(format!("{}/uhm/config.toml", dir))
+    }
+
+    /// Read the configuration from a file. Missing settings keep their defaults.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return Err(format!("Cannot read config {}: {}", path, e)),
+        };
+        let mut config =
+            Self::parse(&content).map_err(|e| format!("Cannot parse config {}: {}", path, e))?;
+        config.path = Some(path.to_string());
+        Ok(config)
+    }
+
+    /// Parse the contents of a configuration file.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let file: ConfigFile = toml::from_str(content).map_err(|e| e.to_string())?;
+
+        let mut config = Self::default();
+        if let Some(count) = file.count_other_keys {
+            config.bindings.count_other_keys = count;
+        }
+        for (key, action) in &file.keys {
+            config.bindings.bind(parse_key(key)?, parse_action(action)?);
+        }
+        if !file.fillers.is_empty() {
+            config.lexicon = Lexicon::new();
+            for (phrase, category) in &file.fillers {
+                config.lexicon.insert(phrase, category);
+            }
+        }
+        Ok(config)
+    }
+
+    /// Format the configuration in the format of the configuration file.
+    pub fn to_toml(&self) -> String {
+        let file = ConfigFile {
+            count_other_keys: Some(self.bindings.count_other_keys),
+            keys: self
+                .bindings
+                .iter()
+                .into_iter()
+                .map(|(key, action)| (key, action_name(action)))
+                .collect(),
+            fillers: self
+                .lexicon
+                .iter()
+                .map(|(phrase, category)| (phrase, category.clone()))
+                .collect(),
+        };
+        toml::to_string(&file).unwrap_or_default()
+    }
+}
+
+/// Parse a key name. Single characters stand for themselves, other keys are named
+/// `Enter`, `Tab`, `Backspace`, `Delete`, `Insert`, `Escape`, `Space`, `Home`, `End`,
+/// `PageUp`, `PageDown`, `ArrowLeft`, `ArrowRight`, `ArrowUp` and `ArrowDown`.
+pub fn parse_key(name: &str) -> Result<Key, String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(Key::Char(c));
+    }
+
+    let key = match name.to_lowercase().as_str() {
+        "enter" | "return" => Key::Enter,
+        "tab" => Key::Tab,
+        "backtab" => Key::BackTab,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Del,
+        "insert" => Key::Insert,
+        "escape" | "esc" => Key::Escape,
+        "space" => Key::Char(' '),
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "arrowleft" | "left" => Key::ArrowLeft,
+        "arrowright" | "right" => Key::ArrowRight,
+        "arrowup" | "up" => Key::ArrowUp,
+        "arrowdown" | "down" => Key::ArrowDown,
+        _ => return Err(format!("unknown key '{}'", name)),
+    };
+    Ok(key)
+}
+
+/// The name of a key as accepted by [parse_key].
+pub fn key_name(key: &Key) -> String {
+    let name = match key {
+        Key::Char(' ') => "Space",
+        Key::Char(c) => return c.to_string(),
+        Key::Enter => "Enter",
+        Key::Tab => "Tab",
+        Key::BackTab => "BackTab",
+        Key::Backspace => "Backspace",
+        Key::Del => "Delete",
+        Key::Insert => "Insert",
+        Key::Escape => "Escape",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::PageUp => "PageUp",
+        Key::PageDown => "PageDown",
+        Key::ArrowLeft => "ArrowLeft",
+        Key::ArrowRight => "ArrowRight",
+        Key::ArrowUp => "ArrowUp",
+        Key::ArrowDown => "ArrowDown",
+        other => return format!("{:?}", other),
+    };
+    name.to_string()
+}
+
+/// Parse the action of a key binding. Returns `None` for `none`.
+pub fn parse_action(name: &str) -> Result<Option<Action>, String> {
+    if let Some(category) = name.strip_prefix("category:") {
+        if category.is_empty() {
+            return Err("category name must not be empty".to_string());
+        }
+        return Ok(Some(Action::Uhm {
+            category: Some(category.to_string()),
+        }));
+    }
+
+    if let Some(speaker) = name.strip_prefix("speaker:") {
+        if speaker.is_empty() {
+            return Err("speaker name must not be empty".to_string());
+        }
+        return Ok(Some(Action::Speaker {
+            name: speaker.to_string(),
+        }));
+    }
+
+    let action = match name {
+        "count" => Action::Uhm { category: None },
+        "undo" => Action::Undo,
+        "redo" => Action::Redo,
+        "pause" => Action::TogglePause,
+        "marker" => Action::Marker { label: None },
+        "stop" => Action::Stop,
+        "none" => return Ok(None),
+        _ => return Err(format!("unknown action '{}'", name)),
+    };
+    Ok(Some(action))
+}
+
+/// The name of an action as accepted by [parse_action].
+pub fn action_name(action: &Action) -> String {
+    match action {
+        Action::Uhm { category: None } => "count".to_string(),
+        Action::Uhm { category: Some(c) } => format!("category:{}", c),
+        Action::Undo => "undo".to_string(),
+        Action::Redo => "redo".to_string(),
+        Action::Pause | Action::Resume | Action::TogglePause => "pause".to_string(),
+        Action::Marker { .. } => "marker".to_string(),
+        Action::Speaker { name } => format!("speaker:{}", name),
+        Action::Stop => "stop".to_string(),
+    }
+}
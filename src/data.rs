@@ -7,26 +7,364 @@ use crate::stats::UhmStats;
 /// The core datastructure of the crate. It stores all data related to a uhm data series.
 ///
 /// It does _not_ store the actual times at which the uhms occured. Instead, it stores
-/// the start time and ordered offsets for each uhm ([Self::data]).
+/// the start time and ordered offsets for each uhm ([Self::data]). The absolute times
+/// are calculated by [Self::event_times]. Series recorded with a finer [Precision] than
+/// milliseconds additionally store their offsets from the start in [Self::times].
 ///
 /// There are also some optional attributes which do not relate to the data directly
 /// but can improve documentation/relationships.
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct Uhms {
-    /// The timestamp at which the presentation started.
-    #[serde(with = "uhm_serde")]
+    /// A unique identifier of this dataset. Datasets read without one are assigned an ID
+    /// derived from their content each time they are parsed, which is kept once they are
+    /// written back, see [crate::schema::assign_ids].
+    #[serde(default)]
+    pub id: String,
+    /// The timestamp at which the presentation started. Written in the [Self::precision] of
+    /// the series.
+    #[serde(deserialize_with = "uhm_serde::deserialize")]
     pub start: DateTime<Utc>,
-    /// The timestamp at which the presentation ended.
-    #[serde(with = "uhm_serde")]
+    /// The timestamp at which the presentation ended. Written like [Self::start].
+    #[serde(deserialize_with = "uhm_serde::deserialize")]
     pub end: DateTime<Utc>,
     /// The actual time data. This vector contains the offsets between individual "uhms"
     /// during the presentation.
     pub data: Vec<i64>,
+    /// The time of every uhm relative to [Self::start] in a finer precision than
+    /// [Self::data]. If set, [Self::data] is derived from it, see [Self::set_times].
+    #[serde(default)]
+    pub times: Option<Times>,
+    /// The filler-word category of each uhm in [Self::data], e.g. `"uhm"` or `"like"`.
+    /// This vector is either empty, if the series was recorded without categories, or
+    /// has the same length as [Self::data].
+    #[serde(default)]
+    pub categories: Vec<Option<String>>,
+    /// Spans during which the recording was paused, e.g. for Q&A breaks. Paused time does
+    /// not count towards [Self::active_duration].
+    #[serde(default)]
+    pub pauses: Vec<Span>,
+    /// Markers which split the recording into sections, e.g. one per slide or chapter of a
+    /// talk. Ordered by their offset.
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+    /// Changes of the current speaker, e.g. in panel discussions. Every turn lasts until
+    /// the next one or the end of the recording. Ordered by their offset.
+    #[serde(default)]
+    pub turns: Vec<Turn>,
+    /// The planned duration of the presentation in milliseconds, e.g. for time-boxed talks.
+    #[serde(default)]
+    pub planned: Option<i64>,
     /// The name of this dataset. Is not required to, but should be, unique through all
     /// data sets. A dataset is not required to have a name.
     pub name: Option<String>,
     /// Optional notes for the dataset.
     pub notes: Option<String>,
+    /// Structured information about the presentation, e.g. for filtering and grouping.
+    #[serde(default)]
+    pub meta: Metadata,
+}
+
+// Serialized by hand because the format of the start and end depends on the precision.
+impl Serialize for Uhms {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        let precision = self.precision();
+        let mut state = serializer.serialize_struct("Uhms", 13)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("start", &uhm_serde::format(&self.start, precision))?;
+        state.serialize_field("end", &uhm_serde::format(&self.end, precision))?;
+        state.serialize_field("data", &self.data)?;
+        match &self.times {
+            Some(times) => state.serialize_field("times", times)?,
+            None => state.skip_field("times")?,
+        }
+        match self.categories.is_empty() {
+            false => state.serialize_field("categories", &self.categories)?,
+            true => state.skip_field("categories")?,
+        }
+        match self.pauses.is_empty() {
+            false => state.serialize_field("pauses", &self.pauses)?,
+            true => state.skip_field("pauses")?,
+        }
+        match self.markers.is_empty() {
+            false => state.serialize_field("markers", &self.markers)?,
+            true => state.skip_field("markers")?,
+        }
+        match self.turns.is_empty() {
+            false => state.serialize_field("turns", &self.turns)?,
+            true => state.skip_field("turns")?,
+        }
+        match &self.planned {
+            Some(planned) => state.serialize_field("planned", planned)?,
+            None => state.skip_field("planned")?,
+        }
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("notes", &self.notes)?;
+        match self.meta.is_empty() {
+            false => state.serialize_field("meta", &self.meta)?,
+            true => state.skip_field("meta")?,
+        }
+        state.end()
+    }
+}
+
+/// The unit of the offsets in [Times].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    #[default]
+    #[serde(rename = "ms")]
+    #[value(name = "ms")]
+    Milliseconds,
+    #[serde(rename = "us")]
+    #[value(name = "us")]
+    Microseconds,
+    #[serde(rename = "ns")]
+    #[value(name = "ns")]
+    Nanoseconds,
+}
+
+impl Precision {
+    /// The number of units in a millisecond.
+    pub fn per_millisecond(self) -> i64 {
+        match self {
+            Self::Milliseconds => 1,
+            Self::Microseconds => 1_000,
+            Self::Nanoseconds => 1_000_000,
+        }
+    }
+
+    /// Express a time span in this precision, rounded down. Spans too long to be
+    /// represented are clamped.
+    pub fn from_delta(self, delta: TimeDelta) -> i64 {
+        let value = match self {
+            Self::Milliseconds => Some(delta.num_milliseconds()),
+            Self::Microseconds => delta.num_microseconds(),
+            Self::Nanoseconds => delta.num_nanoseconds(),
+        };
+        // -i64::MAX is the least value every precision can turn back into a span
+        let Some(value) = value else {
+            return if delta < TimeDelta::zero() {
+                -i64::MAX
+            } else {
+                i64::MAX
+            };
+        };
+        // num_* round towards zero, which only differs for negative spans
+        if self.to_delta(value) > delta {
+            value - 1
+        } else {
+            value
+        }
+    }
+
+    /// The time span of a value in this precision.
+    pub fn to_delta(self, value: i64) -> TimeDelta {
+        match self {
+            Self::Milliseconds => TimeDelta::milliseconds(value),
+            Self::Microseconds => TimeDelta::microseconds(value),
+            Self::Nanoseconds => TimeDelta::nanoseconds(value),
+        }
+    }
+
+    /// Convert a value given in another precision into this one, rounded down.
+    pub fn convert(self, value: i64, from: Precision) -> i64 {
+        let (to, from) = (self.per_millisecond(), from.per_millisecond());
+        if to >= from {
+            value.saturating_mul(to / from)
+        } else {
+            value.div_euclid(from / to)
+        }
+    }
+}
+
+/// The times of the uhms of a [Uhms] series as offsets from [Uhms::start]. Unlike the
+/// deltas in [Uhms::data], every uhm can be accessed directly and the precision is not
+/// limited to milliseconds.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Times {
+    /// The unit of [Self::offsets].
+    pub precision: Precision,
+    /// The time of every uhm relative to [Uhms::start]. Ordered like [Uhms::data].
+    pub offsets: Vec<i64>,
+}
+
+impl Times {
+    /// Convert the delta-encoded millisecond offsets of [Uhms::data]. This is lossless.
+    pub fn from_data(data: &[i64], precision: Precision) -> Self {
+        let mut prev = 0;
+        let offsets = data
+            .iter()
+            .map(|offset| {
+                prev += offset;
+                precision.convert(prev, Precision::Milliseconds)
+            })
+            .collect();
+        Self { precision, offsets }
+    }
+
+    /// Convert into delta-encoded millisecond offsets like [Uhms::data]. Every time is
+    /// rounded down to the millisecond on its own, so rounding errors do not add up.
+    /// Together with [Self::from_data] this is lossless for millisecond precision.
+    pub fn to_data(&self) -> Vec<i64> {
+        let mut prev = 0;
+        self.offsets
+            .iter()
+            .map(|offset| {
+                let offset = Precision::Milliseconds.convert(*offset, self.precision);
+                let delta = offset - prev;
+                prev = offset;
+                delta
+            })
+            .collect()
+    }
+
+    /// Convert into another precision. Converting into a coarser precision rounds down.
+    pub fn with_precision(&self, precision: Precision) -> Self {
+        Self {
+            precision,
+            offsets: self
+                .offsets
+                .iter()
+                .map(|o| precision.convert(*o, self.precision))
+                .collect(),
+        }
+    }
+}
+
+/// Structured information about the presentation of a [Uhms] series.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    /// The person giving the presentation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// The event or venue of the presentation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    /// The title of the talk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The language of the talk, e.g. `en` or `de`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Free-form tags, e.g. `lightning-talk`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The number of people in the audience.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<u32>,
+    /// Whether the presentation was a rehearsal.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub rehearsal: bool,
+}
+
+impl Metadata {
+    /// The keys accepted by [Self::values].
+    pub const KEYS: &[&str] = &[
+        "speaker",
+        "event",
+        "title",
+        "language",
+        "tag",
+        "audience",
+        "rehearsal",
+    ];
+
+    /// Whether no metadata is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The values of a metadata key as strings, e.g. for filtering and grouping. Tags have
+    /// one value per tag, unset keys have none. Returns `None` for unknown keys.
+    pub fn values(&self, key: &str) -> Option<Vec<String>> {
+        let values = match key {
+            "speaker" => self.speaker.iter().cloned().collect(),
+            "event" => self.event.iter().cloned().collect(),
+            "title" => self.title.iter().cloned().collect(),
+            "language" => self.language.iter().cloned().collect(),
+            "tag" | "tags" => self.tags.clone(),
+            "audience" => self.audience.iter().map(|a| a.to_string()).collect(),
+            "rehearsal" => vec![self.rehearsal.to_string()],
+            _ => return None,
+        };
+        Some(values)
+    }
+
+    /// Whether a metadata key has the given value. An empty value matches unset keys.
+    pub fn matches(&self, key: &str, value: &str) -> Result<bool, String> {
+        let Some(values) = self.values(key) else {
+            return Err(format!(
+                "unknown metadata key '{}', expected one of {}",
+                key,
+                Self::KEYS.join(", ")
+            ));
+        };
+        Ok(if value.is_empty() {
+            values.is_empty()
+        } else {
+            values.iter().any(|v| v == value)
+        })
+    }
+}
+
+/// A time span inside a [Uhms] series. Both ends are given in milliseconds relative to
+/// [Uhms::start].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Span {
+    /// The length of this span in milliseconds.
+    pub fn len(&self) -> i64 {
+        self.end - self.start
+    }
+
+    /// Whether this span has no length.
+    pub fn is_empty(&self) -> bool {
+        self.len() <= 0
+    }
+
+    /// Calculate how many milliseconds of this span lie inside `[from, to)`.
+    pub fn overlap(&self, from: i64, to: i64) -> i64 {
+        (self.end.min(to) - self.start.max(from)).max(0)
+    }
+}
+
+/// Marks the start of a new section inside a [Uhms] series.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Marker {
+    /// The time at which the section starts in milliseconds relative to [Uhms::start].
+    pub offset: i64,
+    /// An optional label for the section starting at this marker.
+    pub label: Option<String>,
+}
+
+/// Marks the point at which a speaker takes over inside a [Uhms] series.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Turn {
+    /// The time at which the speaker starts talking in milliseconds relative to [Uhms::start].
+    pub offset: i64,
+    /// The name of the speaker.
+    pub speaker: String,
 }
 
 impl Uhms {
@@ -35,6 +373,188 @@ impl Uhms {
         self.end - self.start
     }
 
+    /// Calculate the total time during which the recording was paused.
+    pub fn paused(&self) -> TimeDelta {
+        TimeDelta::milliseconds(self.pauses.iter().map(Span::len).sum())
+    }
+
+    /// Calculate the time span of this series without any paused time.
+    pub fn active_duration(&self) -> TimeDelta {
+        self.duration() - self.paused()
+    }
+
+    /// Calculate how much longer (positive) or shorter (negative) the presentation took
+    /// than [Self::planned], excluding pauses. Is `None` if there was no plan.
+    pub fn overtime(&self) -> Option<TimeDelta> {
+        self.planned
+            .map(|planned| self.active_duration() - TimeDelta::milliseconds(planned))
+    }
+
+    /// Calculate the number of paused milliseconds inside `[from, to)`, both given in
+    /// milliseconds relative to [Self::start].
+    pub fn paused_between(&self, from: i64, to: i64) -> i64 {
+        self.pauses.iter().map(|p| p.overlap(from, to)).sum()
+    }
+
+    /// Like [Self::data], but with paused time removed from the offsets.
+    pub fn active_data(&self) -> Vec<i64> {
+        let mut prev = 0;
+        self.data
+            .iter()
+            .map(|offset| {
+                let next = prev + offset;
+                let active = offset - self.paused_between(prev, next);
+                prev = next;
+                active
+            })
+            .collect()
+    }
+
+    /// Retrieve the category of the uhm at `index` in [Self::data], if it has one.
+    pub fn category(&self, index: usize) -> Option<&String> {
+        self.categories.get(index).and_then(|c| c.as_ref())
+    }
+
+    /// List all categories used in this series in order of their first occurrence.
+    pub fn category_names(&self) -> Vec<&String> {
+        let mut names = Vec::new();
+        for category in self.categories.iter().flatten() {
+            if !names.contains(&category) {
+                names.push(category);
+            }
+        }
+        names
+    }
+
+    /// Calculate the time of every uhm in milliseconds relative to [Self::start].
+    pub fn offsets(&self) -> Vec<i64> {
+        let mut prev = 0;
+        self.data
+            .iter()
+            .map(|offset| {
+                prev += offset;
+                prev
+            })
+            .collect()
+    }
+
+    /// Retrieve the time of every uhm relative to [Self::start] in the given precision.
+    /// Uses [Self::times] if it is set and [Self::data] otherwise.
+    pub fn times(&self, precision: Precision) -> Times {
+        match &self.times {
+            Some(times) => times.with_precision(precision),
+            None => Times::from_data(&self.data, precision),
+        }
+    }
+
+    /// Calculate the absolute time of every uhm in the best available precision.
+    pub fn event_times(&self) -> Vec<DateTime<Utc>> {
+        let times = self.times(self.precision());
+        times
+            .offsets
+            .iter()
+            .map(|offset| self.start + times.precision.to_delta(*offset))
+            .collect()
+    }
+
+    /// The precision in which the times of the uhms are stored.
+    pub fn precision(&self) -> Precision {
+        self.times.as_ref().map(|t| t.precision).unwrap_or_default()
+    }
+
+    /// Replace the times of all uhms. [Self::data] is derived from `times`, which are only
+    /// kept in [Self::times] if they are finer than milliseconds. Categories are kept.
+    pub fn set_times(&mut self, times: Times) {
+        self.data = times.to_data();
+        self.times = (times.precision > Precision::Milliseconds).then_some(times);
+    }
+
+    /// Replace the times of all uhms with absolute times, stored in the given precision.
+    /// See [Self::set_times].
+    pub fn set_event_times(&mut self, times: &[DateTime<Utc>], precision: Precision) {
+        let offsets = times
+            .iter()
+            .map(|time| precision.from_delta(*time - self.start))
+            .collect();
+        self.set_times(Times { precision, offsets });
+    }
+
+    /// Split the recording into sections at its [Self::markers]. Each section is returned
+    /// with its label, taken from the marker it starts at. The first section starts at
+    /// the beginning of the recording and has no label.
+    ///
+    /// If there are no markers, the whole recording is a single section.
+    pub fn sections(&self) -> Vec<(Span, Option<&String>)> {
+        let end = self.duration().num_milliseconds();
+        let mut sections = Vec::with_capacity(self.markers.len() + 1);
+        let mut start = 0;
+        let mut label = None;
+
+        for marker in &self.markers {
+            sections.push((
+                Span {
+                    start,
+                    end: marker.offset,
+                },
+                label,
+            ));
+            start = marker.offset;
+            label = marker.label.as_ref();
+        }
+        sections.push((Span { start, end }, label));
+
+        sections
+    }
+
+    /// Split the recording into the talking intervals of its speakers at its [Self::turns].
+    /// Each interval is returned with its speaker. Time before the first turn has no
+    /// speaker.
+    ///
+    /// If there are no turns, the whole recording is a single interval without speaker.
+    pub fn speaker_turns(&self) -> Vec<(Span, Option<&String>)> {
+        let end = self.duration().num_milliseconds();
+        let mut turns = Vec::with_capacity(self.turns.len() + 1);
+        let mut start = 0;
+        let mut speaker = None;
+
+        for turn in &self.turns {
+            turns.push((
+                Span {
+                    start,
+                    end: turn.offset,
+                },
+                speaker,
+            ));
+            start = turn.offset;
+            speaker = Some(&turn.speaker);
+        }
+        turns.push((Span { start, end }, speaker));
+
+        turns
+    }
+
+    /// Retrieve the speaker who was talking when the uhm at `index` in [Self::data]
+    /// occurred. Uhms which occur exactly at a turn belong to the new speaker.
+    pub fn speaker(&self, index: usize) -> Option<&String> {
+        let offset = *self.offsets().get(index)?;
+        self.turns
+            .iter()
+            .take_while(|turn| turn.offset <= offset)
+            .last()
+            .map(|turn| &turn.speaker)
+    }
+
+    /// List all speakers of this series in order of their first turn.
+    pub fn speaker_names(&self) -> Vec<&String> {
+        let mut names = Vec::new();
+        for turn in &self.turns {
+            if !names.contains(&&turn.speaker) {
+                names.push(&turn.speaker);
+            }
+        }
+        names
+    }
+
     /// Calculate all stats for this series. See [uhm::stats] for more information.
     pub fn stats(&self) -> UhmStats {
         UhmStats::new(self)
@@ -43,16 +563,22 @@ impl Uhms {
 
 mod uhm_serde {
     use chrono::{DateTime, Utc};
-    use serde::{Deserializer, Serializer};
+    use serde::Deserializer;
 
-    const DATE: &str = "%F %T %z";
+    use super::Precision;
 
-    /// Serialize a `DateTime<Utc>` into the format of [DATE] using [serde].
-    pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&value.format(DATE).to_string())
+    /// Fractions of a second are optional when reading.
+    const DATE: &str = "%F %T%.f %z";
+
+    /// Format a `DateTime<Utc>` like [DATE] with as many fractional digits as `precision`
+    /// has, rounded down.
+    pub fn format(value: &DateTime<Utc>, precision: Precision) -> String {
+        let format = match precision {
+            Precision::Milliseconds => "%F %T%.3f %z",
+            Precision::Microseconds => "%F %T%.6f %z",
+            Precision::Nanoseconds => "%F %T%.9f %z",
+        };
+        value.format(format).to_string()
     }
 
     /// This struct contains all required implementation for [serde] to be able to deserialize
@@ -0,0 +1,261 @@
+//! Build [Uhms] from timestamps collected after the fact, e.g. while reviewing a video.
+//!
+//! Three formats are supported, see [Format]:
+//!
+//! ```text
+//! # plain text: one timestamp per line, optionally followed by a category
+//! 0:12.5 uhm
+//! 1:03
+//! 2025-01-01T10:02:30Z ah
+//! ```
+//!
+//! ```text
+//! time,category
+//! 12.5,uhm
+//! 63,
+//! ```
+//!
+//! SRT and WebVTT subtitle files are searched for filler words with a [Lexicon], see
+//! [crate::transcript]. Every filler word becomes a uhm at the time of the word.
+//!
+//! Timestamps are either relative to the start of the talk, given as seconds like `12.5`
+//! or as a clock time like `1:02.5`, `01:02:03,250` or `01:02:03.250`, or absolute, given
+//! in RFC 3339 or as `2025-01-01 10:02:30`, which is read as UTC. Relative timestamps of
+//! more than a century are rejected.
+
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+
+use crate::{Uhms, transcript::Lexicon};
+
+/// The latest relative timestamp accepted, a century in seconds.
+const MAX_SECONDS: f64 = 100. * 365.25 * 24. * 3600.;
+
+/// Convert seconds into milliseconds, if they are not negative and at most a century.
+pub fn milliseconds(seconds: f64) -> Option<i64> {
+    (0. ..=MAX_SECONDS)
+        .contains(&seconds)
+        .then(|| (seconds * 1000.).round() as i64)
+}
+
+/// A point in time of an imported uhm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Time {
+    /// Milliseconds since the start of the talk.
+    Relative(i64),
+    Absolute(DateTime<Utc>),
+}
+
+/// A single imported uhm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stamp {
+    pub time: Time,
+    pub category: Option<String>,
+}
+
+/// The format of a timestamp file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// One timestamp per line, optionally followed by a category.
+    Text,
+    /// Comma separated values with the timestamp in the first and an optional category in
+    /// the second column. A header row is skipped.
+    Csv,
+    /// SRT or WebVTT cues, which are searched for filler words.
+    Subtitles,
+}
+
+impl Format {
+    /// Guess the format from the extension of a file. Defaults to [Format::Text].
+    pub fn detect(path: &str) -> Self {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        match extension.as_deref() {
+            Some("csv") => Self::Csv,
+            Some("srt") | Some("vtt") => Self::Subtitles,
+            _ => Self::Text,
+        }
+    }
+}
+
+/// Parse the contents of a timestamp file. Subtitle files are searched for the phrases
+/// of `lexicon`.
+pub fn parse(content: &str, format: Format, lexicon: &Lexicon) -> Result<Vec<Stamp>, String> {
+    match format {
+        Format::Text => parse_text(content),
+        Format::Csv => parse_csv(content),
+        Format::Subtitles => {
+            let words = crate::transcript::parse_subtitles(content);
+            let stamps = crate::transcript::detect(&words, lexicon)
+                .into_iter()
+                .map(|(offset, category)| Stamp {
+                    time: Time::Relative(offset),
+                    category: Some(category),
+                })
+                .collect();
+            Ok(stamps)
+        }
+    }
+}
+
+fn parse_text(content: &str) -> Result<Vec<Stamp>, String> {
+    let mut stamps = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // absolute times may contain spaces, so try the longest prefix first
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let parsed = (1..=words.len().min(3))
+            .rev()
+            .find_map(|n| parse_time(&words[..n].join(" ")).map(|time| (time, n)));
+        let Some((time, n)) = parsed else {
+            return Err(format!("line {}: invalid timestamp '{}'", i + 1, words[0]));
+        };
+
+        let category = words[n..].join(" ");
+        stamps.push(Stamp {
+            time,
+            category: (!category.is_empty()).then_some(category),
+        });
+    }
+
+    Ok(stamps)
+}
+
+fn parse_csv(content: &str) -> Result<Vec<Stamp>, String> {
+    let mut stamps = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut columns = line.split(',').map(|c| c.trim().trim_matches('"').trim());
+        let first = columns.next().unwrap_or_default();
+        let Some(time) = parse_time(first) else {
+            if stamps.is_empty() && i == 0 {
+                // header
+                continue;
+            }
+            return Err(format!("line {}: invalid timestamp '{}'", i + 1, first));
+        };
+
+        let category = columns.next().filter(|c| !c.is_empty()).map(str::to_string);
+        stamps.push(Stamp { time, category });
+    }
+
+    Ok(stamps)
+}
+
+/// Parse a single timestamp. See the [module](self) documentation for the accepted forms.
+pub fn parse_time(s: &str) -> Option<Time> {
+    if let Ok(seconds) = s.parse::<f64>() {
+        return milliseconds(seconds).map(Time::Relative);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Some(Time::Absolute(time.to_utc()));
+    }
+    if let Ok(time) = DateTime::parse_from_str(s, "%F %T%.f %z") {
+        return Some(Time::Absolute(time.to_utc()));
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(s, "%F %T%.f") {
+        return Some(Time::Absolute(time.and_utc()));
+    }
+
+    // clock times like 1:02.5 or 01:02:03,250
+    let s = s.replace(',', ".");
+    let parts: Vec<&str> = s.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+    let mut seconds = 0.;
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        let value = if last {
+            part.parse::<f64>().ok()?
+        } else {
+            part.parse::<u32>().ok()? as f64
+        };
+        if !value.is_finite() || value < 0. {
+            return None;
+        }
+        seconds = seconds * 60. + value;
+    }
+    milliseconds(seconds).map(Time::Relative)
+}
+
+/// Build a series from imported uhms. Relative times are relative to `start`, which
+/// defaults to the earliest absolute time or, if there is none, to the current time.
+/// The series ends after `duration` or at its last uhm.
+pub fn to_uhms(
+    stamps: &[Stamp],
+    start: Option<DateTime<Utc>>,
+    duration: Option<TimeDelta>,
+) -> Result<Uhms, String> {
+    let start = start
+        .or_else(|| {
+            stamps
+                .iter()
+                .filter_map(|s| match s.time {
+                    Time::Absolute(time) => Some(time),
+                    Time::Relative(_) => None,
+                })
+                .min()
+        })
+        .unwrap_or_else(Utc::now);
+
+    let mut events: Vec<(i64, Option<String>)> = stamps
+        .iter()
+        .map(|stamp| {
+            let offset = match stamp.time {
+                Time::Relative(offset) => offset,
+                Time::Absolute(time) => (time - start).num_milliseconds(),
+            };
+            (offset, stamp.category.clone())
+        })
+        .collect();
+    events.sort_by_key(|(offset, _)| *offset);
+
+    if let Some((offset, _)) = events.first()
+        && *offset < 0
+    {
+        return Err("found a timestamp before the start".to_string());
+    }
+    let last = events.last().map(|(offset, _)| *offset).unwrap_or(0);
+    let end = match duration {
+        Some(duration) if duration.num_milliseconds() < last => {
+            return Err("found a timestamp after the end".to_string());
+        }
+        Some(duration) => start.checked_add_signed(duration),
+        None => start.checked_add_signed(TimeDelta::milliseconds(last)),
+    };
+    let Some(end) = end else {
+        return Err("the talk ends too far in the future".to_string());
+    };
+
+    let mut prev = 0;
+    let mut data = Vec::with_capacity(events.len());
+    let mut categories = Vec::with_capacity(events.len());
+    for (offset, category) in events {
+        data.push(offset - prev);
+        categories.push(category);
+        prev = offset;
+    }
+    // only keep categories if at least one of them is set
+    if categories.iter().all(|c| c.is_none()) {
+        categories.clear();
+    }
+
+    Ok(Uhms {
+        start,
+        end,
+        data,
+        categories,
+        ..Default::default()
+    })
+}
@@ -1,4 +1,8 @@
-use std::{io::Read, sync::Arc};
+use std::{
+    fs::File,
+    io::{Read, Write},
+    sync::Arc,
+};
 
 pub struct FileDescriptor {
     pub name: String,
@@ -15,15 +19,11 @@ pub enum ReadSource {
 
 impl ReadSource {
     pub fn is_stdin(&self) -> bool {
-        if let Self::Stdin = self { true } else { false }
+        matches!(self, Self::Stdin)
     }
 
     pub fn is_file(&self) -> bool {
-        if let Self::File(_) = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, Self::File(_))
     }
 
     pub fn map<'s, T>(&'s self, stream: T, file: impl FnOnce(&'s String) -> T) -> T {
@@ -87,15 +87,11 @@ pub enum WriteTarget {
 
 impl WriteTarget {
     pub fn is_stdout(&self) -> bool {
-        if let Self::Stdout = self { true } else { false }
+        matches!(self, Self::Stdout)
     }
 
     pub fn is_file(&self) -> bool {
-        if let Self::File(_) = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, Self::File(_))
     }
 
     pub fn map<'s, T>(&'s self, stream: T, file: impl FnOnce(&'s String) -> T) -> T {
@@ -118,8 +114,17 @@ impl WriteTarget {
                 println!("{}", content);
                 Ok(())
             }
-            Self::File(f) => std::fs::write(f, content),
+            Self::File(f) => write_atomic(f, content),
+        }
+    }
+
+    /// Like [Self::write], but first keeps a copy of an existing file as the newest of
+    /// at most `backups` rotating backups, see [backup_path].
+    pub fn replace(&self, content: &str, backups: usize) -> Result<(), Error> {
+        if let Self::File(f) = self {
+            rotate_backups(f, backups)?;
         }
+        self.write(content)
     }
 
     pub fn descriptor(self, content: String) -> FileDescriptor {
@@ -133,20 +138,187 @@ impl WriteTarget {
     }
 }
 
-impl Into<ReadSource> for WriteTarget {
-    fn into(self) -> ReadSource {
-        match self {
+impl From<WriteTarget> for ReadSource {
+    fn from(val: WriteTarget) -> Self {
+        match val {
             WriteTarget::Stdout => ReadSource::Stdin,
             WriteTarget::File(f) => ReadSource::File(f),
         }
     }
 }
 
-impl Into<WriteTarget> for ReadSource {
-    fn into(self) -> WriteTarget {
-        match self {
+impl From<ReadSource> for WriteTarget {
+    fn from(val: ReadSource) -> Self {
+        match val {
             ReadSource::Stdin => WriteTarget::Stdout,
             ReadSource::File(f) => WriteTarget::File(f),
         }
     }
 }
+
+/// Write a file by writing a temporary file next to it and renaming it, so the file is
+/// never left half-written.
+pub fn write_atomic(path: &str, content: &str) -> Result<(), Error> {
+    let path = std::path::Path::new(path);
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("uhm");
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+
+    let written = File::create(&temp).and_then(|mut file| {
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    });
+    match written.and_then(|_| std::fs::rename(&temp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+/// Keep a copy of an existing file as the newest of at most `backups` backups. Older
+/// backups are renamed, the oldest one is removed.
+pub fn rotate_backups(path: &str, backups: usize) -> Result<(), Error> {
+    if backups == 0 || !std::path::Path::new(path).is_file() {
+        return Ok(());
+    }
+    for n in (1..backups).rev() {
+        let older = backup_path(path, n);
+        if std::path::Path::new(&older).is_file() {
+            std::fs::rename(&older, backup_path(path, n + 1))?;
+        }
+    }
+    std::fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+/// The path of the `n`th backup of a file, starting at 1 for the newest one.
+pub fn backup_path(path: &str, n: usize) -> String {
+    format!("{}.bak.{}", path, n)
+}
+
+/// The paths of all existing backups of a file, newest first.
+pub fn backups(path: &str) -> Vec<String> {
+    (1..)
+        .map(|n| backup_path(path, n))
+        .take_while(|backup| std::path::Path::new(backup).is_file())
+        .collect()
+}
+
+/// The extensions of data files, see [data_files].
+pub const DATA_EXTENSIONS: &[&str] = &["json", "jsonl", "ndjson", "db", "sqlite", "sqlite3"];
+
+/// The data files a path refers to. A directory refers to the data files directly in it,
+/// a file name with the wildcards `*` and `?` to the matching data files, both sorted by
+/// name. Data files are recognized by their extension, see [DATA_EXTENSIONS], so backups
+/// and lock files are left out. Other paths refer to themselves, even if they do not exist.
+pub fn data_files(path: &str) -> Result<Vec<String>, Error> {
+    let path_buf = std::path::Path::new(path);
+    let name = path_buf
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let (dir, pattern) = if path_buf.is_dir() {
+        (Some(path_buf), "*")
+    } else if name.contains(['*', '?']) {
+        (
+            path_buf.parent().filter(|p| !p.as_os_str().is_empty()),
+            name,
+        )
+    } else {
+        return Ok(vec![path.to_string()]);
+    };
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir.unwrap_or(std::path::Path::new(".")))? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let is_data = std::path::Path::new(&file_name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| DATA_EXTENSIONS.contains(&e));
+        if is_data
+            && !file_name.starts_with('.')
+            && entry.path().is_file()
+            && wildcard(pattern, &file_name)
+        {
+            files.push(match dir {
+                Some(dir) => dir.join(&file_name).to_string_lossy().into_owned(),
+                None => file_name,
+            });
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether a name matches a pattern, where `*` matches any text and `?` a single character.
+fn wildcard(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // matched[j]: whether the pattern so far matches the first j characters of the name
+    let mut matched = vec![false; name.len() + 1];
+    matched[0] = true;
+    for p in pattern {
+        let previous = matched.clone();
+        matched[0] = p == '*' && previous[0];
+        for j in 1..=name.len() {
+            matched[j] = match p {
+                '*' => previous[j] || matched[j - 1],
+                '?' => previous[j - 1],
+                c => previous[j - 1] && name[j - 1] == c,
+            };
+        }
+    }
+    matched[name.len()]
+}
+
+/// An advisory lock on a file, held until it is dropped. The lock is taken on a separate
+/// hidden lock file next to it, e.g. `.uhm.json.lock`, because files are replaced when they
+/// are written. The lock file is kept, since removing it would let another process lock a
+/// new one while a third still waits on the old one.
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// The path of the lock file for a file.
+    pub fn path_for(path: &str) -> String {
+        let path = std::path::Path::new(path);
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        path.with_file_name(format!(".{}.lock", name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Lock a file, waiting until other processes release it.
+    pub fn acquire(path: &str) -> Result<Self, Error> {
+        let file = Self::open(path)?;
+        file.lock()?;
+        Ok(Self { _file: file })
+    }
+
+    /// Lock a file if no other process holds the lock.
+    pub fn try_acquire(path: &str) -> Result<Option<Self>, Error> {
+        let file = Self::open(path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+            Err(std::fs::TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    fn open(path: &str) -> Result<File, Error> {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(Self::path_for(path))
+    }
+}
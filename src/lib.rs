@@ -1,51 +1,49 @@
 pub mod cli;
 
+pub mod config;
+
 pub mod stats;
 
 mod data;
-pub use data::Uhms;
+pub use data::{Marker, Metadata, Precision, Span, Times, Turn, Uhms};
+
+pub mod import;
 
 pub mod io;
 
+pub mod merge;
+
 pub mod plot;
 
-pub fn record(name: Option<String>, notes: Option<String>) -> Uhms {
-    use chrono::Utc;
-    use console::Term;
-    use std::io::Write;
+pub mod query;
 
-    let before = Utc::now();
-    let mut values = Vec::new();
-    let terminal = Term::stdout();
+pub mod record;
 
-    let mut prev = before.clone();
+pub mod schema;
 
-    loop {
-        let c = terminal.read_char();
+pub mod serve;
 
-        match c {
-            Ok('\n') => break,
-            _ => {
-                let next = chrono::Utc::now();
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
-                values.push((next - prev).num_milliseconds());
-                prev = next;
+pub mod store;
 
-                print!("\r=> {} ", values.len());
-                #[allow(unused_must_use)]
-                std::io::stdout().flush();
-            }
-        }
-    }
+pub mod stream;
 
-    let after = Utc::now();
-    Uhms {
-        start: before,
-        end: after,
-        data: values,
-        name: name,
-        notes: notes,
-    }
+pub mod transcript;
+
+pub mod validate;
+
+pub fn record(
+    name: Option<String>,
+    notes: Option<String>,
+    options: &record::RecordOptions,
+) -> Uhms {
+    let source = record::TerminalSource::new(options);
+    record::Recorder::new(record::SystemClock, source, options.clone())
+        .name(name)
+        .notes(notes)
+        .run()
 }
 
 pub fn plot_uhm(uhm: &crate::Uhms, c: &mut plot::Canvas, options: &plot::PlotOptions) {
@@ -60,22 +58,67 @@ pub fn plot_uhm(uhm: &crate::Uhms, c: &mut plot::Canvas, options: &plot::PlotOpt
     let y = options.y;
     let mut x = 0.;
 
+    let stats = uhm.stats();
+    let mut label = format!("{} ({:.2} uhm/min", name, stats.per_minute);
+    for category in &stats.categories {
+        label.push_str(&format!(
+            "; {}: {}",
+            category.name.as_deref().unwrap_or("other"),
+            category.count
+        ));
+    }
+    label.push(')');
+
     c.draw(
         plot::Text::default()
-            .content(format!("{} ({:.2} uhm/min)", name, crate::stats::per_minute(uhm.data.len(), &uhm.duration())))
+            .content(label)
             .anchor(plot::Anchor::East)
             .at((x - 0.5, y))
-            .stroke(plot::Stroke::default().color(plot::Color::none()))
+            .stroke(plot::Stroke::default().color(plot::Color::none())),
     );
 
+    for pause in &uhm.pauses {
+        c.draw(
+            plot::Rect::default()
+                .start((pause.start as f64 * millisecond_width, y - 0.15))
+                .end((pause.end as f64 * millisecond_width, y + 0.15))
+                .stroke(plot::Stroke::default().color(plot::Color::none()))
+                .fill(options.pause_color.clone()),
+        );
+    }
+
     c.draw(
         plot::Line::default()
             .start((x, y))
             .end((x + ((milliseconds as f64) * millisecond_width), y)),
     );
 
-    for offset in &uhm.data {
-        x += (*offset as f64 / 1000.) * millisecond_width;
-        c.draw(plot::Circle::default().at((x, y)).radius(0.05));
+    for marker in &uhm.markers {
+        let mx = marker.offset as f64 * millisecond_width;
+        c.draw(
+            plot::Line::default()
+                .start((mx, y - 0.3))
+                .end((mx, y + 0.3)),
+        );
+        if let Some(label) = &marker.label {
+            c.draw(
+                plot::Text::default()
+                    .content(label.clone())
+                    .anchor(plot::Anchor::SouthWest)
+                    .at((mx, y + 0.2))
+                    .stroke(plot::Stroke::default().color(plot::Color::none())),
+            );
+        }
+    }
+
+    let names = uhm.category_names();
+    for (i, offset) in uhm.data.iter().enumerate() {
+        x += *offset as f64 * millisecond_width;
+        let mut circle = plot::Circle::default().at((x, y)).radius(0.05);
+        if let Some(category) = uhm.category(i) {
+            let fallback = names.iter().position(|n| *n == category).unwrap_or(0);
+            circle = circle.fill(options.category_color(category, fallback));
+        }
+        c.draw(circle);
     }
 }
@@ -0,0 +1,237 @@
+//! Combine several recordings of the same session, e.g. by multiple observers.
+//!
+//! Recordings are aligned by their [Uhms::start], so every uhm is compared by its
+//! absolute time. Two uhms of different recordings match if they are at most a
+//! tolerance apart.
+
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+
+use crate::Uhms;
+
+/// One of the recordings compared by an [Agreement].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compared {
+    pub id: String,
+    pub name: Option<String>,
+}
+
+impl From<&Uhms> for Compared {
+    fn from(uhms: &Uhms) -> Self {
+        Self {
+            id: uhms.id.clone(),
+            name: uhms.name.clone(),
+        }
+    }
+}
+
+/// How well two recordings of the same session agree. See [agreement].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Agreement {
+    /// The recording treated as the ground truth.
+    pub reference: Compared,
+    /// The recording compared with the reference.
+    pub other: Compared,
+    /// Number of uhms found in both recordings.
+    pub matched: usize,
+    /// Number of uhms only found in the reference recording.
+    pub missed: usize,
+    /// Number of uhms only found in the other recording.
+    pub extra: usize,
+    /// Cohen's kappa for both recordings, see [kappa].
+    pub kappa: f64,
+}
+
+/// Calculate the absolute time of every uhm in milliseconds since the unix epoch.
+pub fn timestamps(uhms: &Uhms) -> Vec<i64> {
+    let start = uhms.start.timestamp_millis();
+    uhms.offsets().into_iter().map(|o| start + o).collect()
+}
+
+/// Match the uhms of two sorted series of timestamps. Two uhms match if they are at most
+/// `tolerance` milliseconds apart, every uhm matches at most one other uhm.
+///
+/// Returns the indices of all matched pairs.
+pub fn match_events(a: &[i64], b: &[i64], tolerance: i64) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        if (a[i] - b[j]).abs() <= tolerance {
+            matches.push((i, j));
+            i += 1;
+            j += 1;
+        } else if a[i] < b[j] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    matches
+}
+
+/// Compare two recordings of the same session. `reference` is treated as the ground
+/// truth, so uhms missing from `other` count as missed and additional ones as extra.
+pub fn agreement(reference: &Uhms, other: &Uhms, tolerance: TimeDelta) -> Agreement {
+    let tolerance = tolerance.num_milliseconds();
+    let a = timestamps(reference);
+    let b = timestamps(other);
+
+    let matched = match_events(&a, &b, tolerance).len();
+    let missed = a.len() - matched;
+    let extra = b.len() - matched;
+
+    // both recordings are split into windows which either contain a uhm or do not
+    let from = reference.start.min(other.start);
+    let to = reference.end.max(other.end);
+    let windows = ((to - from).num_milliseconds() / (2 * tolerance).max(1)) as usize;
+    let neither = windows.saturating_sub(matched + missed + extra);
+
+    Agreement {
+        reference: reference.into(),
+        other: other.into(),
+        matched,
+        missed,
+        extra,
+        kappa: kappa(matched, missed, extra, neither),
+    }
+}
+
+/// Calculate Cohen's kappa from a confusion matrix of two raters deciding whether a
+/// time window contains a uhm.
+///
+/// $ \kappa = \frac{p_o - p_e}{1 - p_e} $
+///
+/// where $p_o$ is the observed agreement and $p_e$ the agreement expected by chance.
+pub fn kappa(both: usize, only_first: usize, only_second: usize, neither: usize) -> f64 {
+    let total = (both + only_first + only_second + neither) as f64;
+    if total == 0. {
+        return 1.;
+    }
+
+    let observed = (both + neither) as f64 / total;
+    let first = (both + only_first) as f64 / total;
+    let second = (both + only_second) as f64 / total;
+    let expected = first * second + (1. - first) * (1. - second);
+
+    if expected >= 1. {
+        1.
+    } else {
+        (observed - expected) / (1. - expected)
+    }
+}
+
+/// Merge several recordings of the same session into a consensus recording.
+///
+/// Uhms of different recordings which are at most `tolerance` apart are grouped. A group
+/// becomes a uhm of the result if it contains uhms of at least `quorum` recordings. Its
+/// time is the average time of the group and its category the most common one.
+///
+/// The result spans all recordings. Pauses, markers and the planned duration are taken
+/// from the first recording.
+pub fn merge(series: &[&Uhms], tolerance: TimeDelta, quorum: usize) -> Uhms {
+    let Some(first) = series.first() else {
+        return Uhms::default();
+    };
+    let tolerance = tolerance.num_milliseconds();
+
+    // all uhms of all recordings as (time, recording, category)
+    let mut events = Vec::new();
+    for (i, uhm) in series.iter().enumerate() {
+        for (j, time) in timestamps(uhm).into_iter().enumerate() {
+            events.push((time, i, uhm.category(j)));
+        }
+    }
+    events.sort_by_key(|(time, _, _)| *time);
+
+    let start = series.iter().map(|u| u.start).min().unwrap_or(first.start);
+    let end = series.iter().map(|u| u.end).max().unwrap_or(first.end);
+
+    let mut consensus: Vec<(i64, Option<String>)> = Vec::new();
+    let mut used = vec![false; events.len()];
+    for i in 0..events.len() {
+        if used[i] {
+            continue;
+        }
+
+        // group with the following uhms of other recordings inside the tolerance
+        let mut group = vec![i];
+        for (j, event) in events.iter().enumerate().skip(i + 1) {
+            if event.0 - events[i].0 > tolerance {
+                break;
+            }
+            if !used[j] && !group.iter().any(|g| events[*g].1 == event.1) {
+                group.push(j);
+            }
+        }
+
+        if group.len() < quorum {
+            used[i] = true;
+            continue;
+        }
+
+        let mut categories: Vec<(Option<&String>, usize)> = Vec::new();
+        let mut sum = 0;
+        for g in &group {
+            used[*g] = true;
+            sum += events[*g].0;
+            match categories.iter_mut().find(|(c, _)| *c == events[*g].2) {
+                Some((_, count)) => *count += 1,
+                None => categories.push((events[*g].2, 1)),
+            }
+        }
+        let category = categories
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .and_then(|(c, _)| c.cloned());
+        consensus.push((sum / group.len() as i64, category));
+    }
+    // averages of overlapping groups may be out of order
+    consensus.sort_by_key(|(time, _)| *time);
+
+    let mut prev = start.timestamp_millis();
+    let mut data = Vec::with_capacity(consensus.len());
+    let mut categories = Vec::with_capacity(consensus.len());
+    for (time, category) in consensus {
+        data.push(time - prev);
+        categories.push(category);
+        prev = time;
+    }
+    if categories.iter().all(|c| c.is_none()) {
+        categories.clear();
+    }
+
+    // pauses and markers are relative to the start of the first recording
+    let shift = (first.start - start).num_milliseconds();
+    let mut pauses = first.pauses.clone();
+    for pause in &mut pauses {
+        pause.start += shift;
+        pause.end += shift;
+    }
+    let mut markers = first.markers.clone();
+    for marker in &mut markers {
+        marker.offset += shift;
+    }
+    let mut turns = first.turns.clone();
+    for turn in &mut turns {
+        turn.offset += shift;
+    }
+
+    Uhms {
+        id: String::new(),
+        start,
+        end,
+        data,
+        times: None,
+        categories,
+        pauses,
+        markers,
+        turns,
+        planned: first.planned,
+        name: first.name.clone(),
+        notes: Some(format!("Merged from {} recordings", series.len())),
+        meta: first.meta.clone(),
+    }
+}
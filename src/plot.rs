@@ -1,9 +1,9 @@
 #[derive(Clone, Debug)]
 pub struct Point(pub f64, pub f64);
 
-impl<X: Into<f64>, Y: Into<f64>> Into<Point> for (X, Y) {
-    fn into(self) -> Point {
-        Point(self.0.into(), self.1.into())
+impl<X: Into<f64>, Y: Into<f64>> From<(X, Y)> for Point {
+    fn from(val: (X, Y)) -> Self {
+        Point(val.0.into(), val.1.into())
     }
 }
 
@@ -13,7 +13,7 @@ impl Default for Point {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Default)]
 pub struct Color(u8, u8, u8, u8);
 
 impl Color {
@@ -26,26 +26,26 @@ impl Color {
     }
 
     pub fn hex(rgb: u32) -> Self {
-        let red = rgb & 0x110000 >> 8;
-        let green = rgb & 0x001100 >> 4;
-        let blue = rgb & 0x000011 >> 0;
+        let red = (rgb >> 16) & 0xFF;
+        let green = (rgb >> 8) & 0xFF;
+        let blue = rgb & 0xFF;
         Color(red as u8, green as u8, blue as u8, 255)
     }
 
     pub fn hexa(rgba: u64) -> Self {
-        let red = rgba & 0x11000000 >> 12;
-        let green = rgba & 0x00110000 >> 8;
-        let blue = rgba & 0x00001100 >> 4;
-        let alpha = rgba & 0x00000011 >> 0;
+        let red = (rgba >> 24) & 0xFF;
+        let green = (rgba >> 16) & 0xFF;
+        let blue = (rgba >> 8) & 0xFF;
+        let alpha = rgba & 0xFF;
         Color(red as u8, green as u8, blue as u8, alpha as u8)
     }
 
     pub fn white() -> Self {
-        Self::hex(0x00_00_00)
+        Self::hex(0xFF_FF_FF)
     }
 
     pub fn black() -> Self {
-        Self::hex(0xFF_FF_FF)
+        Self::hex(0x00_00_00)
     }
 
     pub fn none() -> Self {
@@ -58,12 +58,6 @@ impl Color {
     }
 }
 
-impl Default for Color {
-    fn default() -> Self {
-        Self(0, 0, 0, 0)
-    }
-}
-
 pub type ColorMap = std::collections::HashMap<Color, String>;
 
 #[derive(Debug)]
@@ -71,6 +65,12 @@ pub struct Canvas {
     drawings: Vec<Drawable>,
 }
 
+impl Default for Canvas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> Canvas {
     pub fn new() -> Self {
         Self {
@@ -119,10 +119,10 @@ pub trait Artist {
 
     fn render_any(d: Drawable, cm: &ColorMap) -> Result<Self::PartialOutput, Self::Err> {
         match d {
-            Drawable::Line(l) => Self::render_line(l, &cm),
-            Drawable::Rect(r) => Self::render_rect(r, &cm),
-            Drawable::Circle(c) => Self::render_circle(c, &cm),
-            Drawable::Text(t) => Self::render_text(t, &cm),
+            Drawable::Line(l) => Self::render_line(l, cm),
+            Drawable::Rect(r) => Self::render_rect(r, cm),
+            Drawable::Circle(c) => Self::render_circle(c, cm),
+            Drawable::Text(t) => Self::render_text(t, cm),
         }
     }
 }
@@ -207,6 +207,11 @@ impl Circle {
         self.radius = radius;
         self
     }
+
+    pub fn fill(mut self, fill: Color) -> Self {
+        self.fill = fill;
+        self
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -228,8 +233,31 @@ impl Default for Rect {
     }
 }
 
-#[derive(Clone, Debug)]
+impl Rect {
+    pub fn start<P: Into<Point>>(mut self, point: P) -> Self {
+        self.from = point.into();
+        self
+    }
+
+    pub fn end<P: Into<Point>>(mut self, point: P) -> Self {
+        self.to = point.into();
+        self
+    }
+
+    pub fn stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = stroke;
+        self
+    }
+
+    pub fn fill(mut self, fill: Color) -> Self {
+        self.fill = fill;
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub enum Anchor {
+    #[default]
     Center,
     North,
     South,
@@ -241,12 +269,6 @@ pub enum Anchor {
     West,
 }
 
-impl Default for Anchor {
-    fn default() -> Self {
-        Self::Center
-    }
-}
-
 #[derive(Clone, Debug)]
 pub struct Text {
     pub content: String,
@@ -300,27 +322,27 @@ pub enum Drawable {
     Text(Text),
 }
 
-impl Into<Drawable> for Line {
-    fn into(self) -> Drawable {
-        Drawable::Line(self)
+impl From<Line> for Drawable {
+    fn from(val: Line) -> Self {
+        Drawable::Line(val)
     }
 }
 
-impl Into<Drawable> for Circle {
-    fn into(self) -> Drawable {
-        Drawable::Circle(self)
+impl From<Circle> for Drawable {
+    fn from(val: Circle) -> Self {
+        Drawable::Circle(val)
     }
 }
 
-impl Into<Drawable> for Rect {
-    fn into(self) -> Drawable {
-        Drawable::Rect(self)
+impl From<Rect> for Drawable {
+    fn from(val: Rect) -> Self {
+        Drawable::Rect(val)
     }
 }
 
-impl Into<Drawable> for Text {
-    fn into(self) -> Drawable {
-        Drawable::Text(self)
+impl From<Text> for Drawable {
+    fn from(val: Text) -> Self {
+        Drawable::Text(val)
     }
 }
 
@@ -429,6 +451,7 @@ const TIKZ_EPILOG: &str = "\
 ";
 
 impl TikZ {
+    #[allow(clippy::result_unit_err)]
     pub fn render_doc(c: Canvas) -> Result<String, ()> {
         let mut buf = TIKZ_PREAMBLE.to_string();
         let rendered = Self::render(c).unwrap();
@@ -545,6 +568,7 @@ import cetz.draw: *
 const CETZ_EPILOG: &str = "})\n";
 
 impl CeTZ {
+    #[allow(clippy::result_unit_err)]
     pub fn render_doc(c: Canvas) -> Result<String, ()> {
         let mut buf = CETZ_PREAMBLE.to_string();
         let rendered = Self::render(c).unwrap();
@@ -557,6 +581,28 @@ impl CeTZ {
 pub struct PlotOptions {
     pub second_width: f64,
     pub y: f64,
+    /// Categories in the order in which they are assigned colors from [Self::palette].
+    /// Categories not contained here are colored by their order inside the plotted series.
+    pub categories: Vec<String>,
+    /// Fill colors for categorized uhms.
+    pub palette: Vec<Color>,
+    /// Fill color for spans during which a recording was paused.
+    pub pause_color: Color,
+}
+
+impl PlotOptions {
+    /// Get the fill color for uhms of the given category.
+    pub fn category_color(&self, category: &String, fallback: usize) -> Color {
+        if self.palette.is_empty() {
+            return Color::none();
+        }
+        let index = self
+            .categories
+            .iter()
+            .position(|c| c == category)
+            .unwrap_or(self.categories.len() + fallback);
+        self.palette[index % self.palette.len()].clone()
+    }
 }
 
 impl Default for PlotOptions {
@@ -564,6 +610,16 @@ impl Default for PlotOptions {
         Self {
             second_width: 1. / 60.,
             y: 0.0,
+            categories: Vec::new(),
+            palette: vec![
+                Color::hex(0x1F_77_B4),
+                Color::hex(0xFF_7F_0E),
+                Color::hex(0x2C_A0_2C),
+                Color::hex(0xD6_27_28),
+                Color::hex(0x94_67_BD),
+                Color::hex(0x8C_56_4B),
+            ],
+            pause_color: Color::hex(0xD0_D0_D0),
         }
     }
 }
@@ -0,0 +1,38 @@
+//! Criteria for selecting recordings by name, date range and tags.
+//!
+//! A [Query] is either evaluated in memory with [Query::matches] or, for recordings stored
+//! in a database, translated into an indexed lookup.
+
+use chrono::{DateTime, Utc};
+
+use crate::Uhms;
+
+/// Selects recordings matching all of its criteria. An empty query matches everything.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Query {
+    /// The exact name of the recordings.
+    pub name: Option<String>,
+    /// Only recordings which started at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only recordings which started before this time.
+    pub before: Option<DateTime<Utc>>,
+    /// Tags which the recordings must all have.
+    pub tags: Vec<String>,
+}
+
+impl Query {
+    /// Whether the query matches every recording.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether a recording matches all criteria of the query.
+    pub fn matches(&self, uhm: &Uhms) -> bool {
+        self.name
+            .as_ref()
+            .is_none_or(|name| uhm.name.as_ref() == Some(name))
+            && self.since.is_none_or(|since| uhm.start >= since)
+            && self.before.is_none_or(|before| uhm.start < before)
+            && self.tags.iter().all(|tag| uhm.meta.tags.contains(tag))
+    }
+}
@@ -0,0 +1,917 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Write,
+    sync::{Arc, Mutex, mpsc},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use console::Key;
+use serde::{Deserialize, Serialize};
+
+use crate::{Marker, Precision, Span, Turn, Uhms, config::Bindings};
+
+/// Options for a live recording with [crate::record].
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+    /// Maps keys to filler-word categories, e.g. `'u'` to `"uhm"` and `'a'` to `"ah"`.
+    /// Keys without a mapping fall back to [Self::bindings]. Takes precedence over them.
+    pub categories: HashMap<char, String>,
+    /// Maps keys to actions, usually read from the [configuration](crate::config).
+    pub bindings: Bindings,
+    /// The speakers of a panel discussion. The number keys `1` to `9` switch to the speaker
+    /// at that position. The first speaker talks from the start of the recording.
+    pub speakers: Vec<String>,
+    /// How often the status line is refreshed while no key is pressed.
+    pub refresh: Duration,
+    /// The planned duration of the presentation. The remaining time is shown while
+    /// recording and the recording stops once it is used up, unless [Self::overtime] is set.
+    pub duration: Option<TimeDelta>,
+    /// Keep recording after [Self::duration] has passed.
+    pub overtime: bool,
+    /// A file to which every action is written as it happens. See [Journal].
+    pub journal: Option<String>,
+    /// The start of the recording. Defaults to the time at which the recording begins.
+    pub start: Option<DateTime<Utc>>,
+    /// The precision in which the times of the uhms are stored, see [Uhms::times].
+    pub precision: Precision,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        Self {
+            categories: HashMap::new(),
+            bindings: Bindings::default(),
+            speakers: Vec::new(),
+            refresh: Duration::from_millis(500),
+            duration: None,
+            overtime: false,
+            journal: None,
+            start: None,
+            precision: Precision::default(),
+        }
+    }
+}
+
+impl RecordOptions {
+    /// Look up the category assigned to the given key.
+    pub fn category(&self, key: char) -> Option<&String> {
+        self.categories.get(&key)
+    }
+
+    /// Look up the speaker selected by the given key, `'1'` for the first one and so on.
+    pub fn speaker(&self, key: char) -> Option<&String> {
+        match key.to_digit(10) {
+            Some(n) if n > 0 => self.speakers.get(n as usize - 1),
+            _ => None,
+        }
+    }
+
+    /// Whether a session has used up its planned duration and should be stopped.
+    pub fn is_over(&self, session: &Session, now: DateTime<Utc>) -> bool {
+        match self.duration {
+            Some(duration) => !self.overtime && session.elapsed(now) >= duration,
+            None => false,
+        }
+    }
+}
+
+/// An action performed during a recording, usually in response to a key press.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum Action {
+    /// Record a uhm, optionally of a given category.
+    Uhm {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        category: Option<String>,
+    },
+    /// Remove the last recorded uhm.
+    Undo,
+    /// Restore the last removed uhm.
+    Redo,
+    /// Pause the recording.
+    Pause,
+    /// Resume the recording.
+    Resume,
+    /// Pause the recording if it is running, resume it otherwise.
+    #[serde(rename = "toggle-pause")]
+    TogglePause,
+    /// Start a new section, optionally with a label.
+    Marker {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+    },
+    /// Hand over to another speaker.
+    Speaker { name: String },
+    /// Stop the recording.
+    Stop,
+}
+
+/// A source for the current time of a [Recorder].
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock which only moves when it is told to, e.g. for replaying recordings in tests.
+/// Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock(Arc<Mutex<DateTime<Utc>>>);
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self(Arc::new(Mutex::new(now)))
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        if let Ok(mut time) = self.0.lock() {
+            *time = now;
+        }
+    }
+
+    pub fn advance(&self, delta: TimeDelta) {
+        if let Ok(mut time) = self.0.lock() {
+            *time += delta;
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        match self.0.lock() {
+            Ok(time) => *time,
+            Err(e) => *e.into_inner(),
+        }
+    }
+}
+
+/// The result of waiting for an [EventSource].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// An action happened. Actions without a time happened at the current time of the
+    /// [Recorder]'s clock.
+    Action(Option<DateTime<Utc>>, Action),
+    /// Nothing happened before the timeout.
+    Idle,
+    /// The source has no more actions. The recording stops.
+    Closed,
+}
+
+/// A source of actions for a [Recorder], e.g. the keyboard or a network connection.
+pub trait EventSource {
+    /// Wait at most `timeout` for the next action.
+    fn next(&mut self, timeout: Duration) -> Input;
+
+    /// Called when the recording stops on its own, i.e. when its planned duration is used
+    /// up, to give the source a chance to clean up.
+    fn finish(&mut self) {}
+}
+
+/// Sources which run on another thread send timestamped actions through a channel.
+impl EventSource for mpsc::Receiver<(DateTime<Utc>, Action)> {
+    fn next(&mut self, timeout: Duration) -> Input {
+        match self.recv_timeout(timeout) {
+            Ok((time, action)) => Input::Action(Some(time), action),
+            Err(mpsc::RecvTimeoutError::Timeout) => Input::Idle,
+            Err(mpsc::RecvTimeoutError::Disconnected) => Input::Closed,
+        }
+    }
+}
+
+/// Map a key press to its action. Returns `None` for keys without an action.
+///
+/// Characters with a [category](RecordOptions::categories) record a uhm of that category
+/// and number keys switch to a [speaker](RecordOptions::speakers), if there is one. All
+/// other keys are looked up in [RecordOptions::bindings]. By default:
+///
+/// | Key                      | Action                         |
+/// |--------------------------|--------------------------------|
+/// | Enter                    | [Action::Stop]                 |
+/// | Tab                      | [Action::TogglePause]          |
+/// | Backspace                | [Action::Undo]                 |
+/// | Delete                   | [Action::Redo]                 |
+/// | Right arrow, Page Down   | [Action::Marker]               |
+/// | Any other character      | [Action::Uhm] without category |
+pub fn key_action(key: &Key, options: &RecordOptions) -> Option<Action> {
+    if let Key::Char(c) = key
+        && let Some(category) = options.category(*c)
+    {
+        return Some(Action::Uhm {
+            category: Some(category.clone()),
+        });
+    }
+    if let Key::Char(c) = key
+        && let Some(speaker) = options.speaker(*c)
+    {
+        return Some(Action::Speaker {
+            name: speaker.clone(),
+        });
+    }
+    options.bindings.action(key)
+}
+
+/// Reads key presses from the terminal, see [key_action].
+///
+/// Keys are read on a separate thread, so the status line can be refreshed while no
+/// key is pressed. The thread ends after [Action::Stop] or, if the recording stops on its
+/// own, after the next key, see [EventSource::finish].
+pub struct TerminalSource {
+    actions: mpsc::Receiver<(DateTime<Utc>, Action)>,
+    reader: Option<std::thread::JoinHandle<()>>,
+}
+
+impl TerminalSource {
+    pub fn new(options: &RecordOptions) -> Self {
+        let (sender, actions) = mpsc::channel();
+        let reader = console::Term::stdout();
+        let options = options.clone();
+
+        let reader = std::thread::spawn(move || {
+            loop {
+                let action = match reader.read_key() {
+                    Ok(Key::Unknown) if !reader.is_term() => Action::Stop,
+                    Ok(key) => match key_action(&key, &options) {
+                        Some(action) => action,
+                        None => continue,
+                    },
+                    Err(_) => Action::Stop,
+                };
+                let stop = action == Action::Stop;
+                if sender.send((Utc::now(), action)).is_err() || stop {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            actions,
+            reader: Some(reader),
+        }
+    }
+}
+
+impl EventSource for TerminalSource {
+    fn next(&mut self, timeout: Duration) -> Input {
+        self.actions.next(timeout)
+    }
+
+    /// Wait for the reader to give the terminal back, which it only does after a key press.
+    fn finish(&mut self) {
+        println!("Press any key to continue.");
+        // without a receiver, the next key ends the reader
+        self.actions = mpsc::channel().1;
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Replays a fixed list of timestamped actions. Every action moves the given clock to
+/// its time, so a [Recorder] using the same clock sees them as if they happened live.
+#[derive(Debug, Clone)]
+pub struct ScriptedSource {
+    clock: ManualClock,
+    actions: VecDeque<(DateTime<Utc>, Action)>,
+}
+
+impl ScriptedSource {
+    pub fn new(clock: ManualClock, actions: Vec<(DateTime<Utc>, Action)>) -> Self {
+        Self {
+            clock,
+            actions: actions.into(),
+        }
+    }
+
+    /// Replay key presses given as milliseconds relative to the current time of `clock`.
+    /// Keys are mapped to actions by [key_action].
+    pub fn keys(clock: ManualClock, keys: &[(i64, Key)], options: &RecordOptions) -> Self {
+        let start = clock.now();
+        let actions = keys
+            .iter()
+            .filter_map(|(offset, key)| {
+                key_action(key, options)
+                    .map(|action| (start + TimeDelta::milliseconds(*offset), action))
+            })
+            .collect();
+        Self::new(clock, actions)
+    }
+}
+
+impl EventSource for ScriptedSource {
+    fn next(&mut self, _timeout: Duration) -> Input {
+        match self.actions.pop_front() {
+            Some((time, action)) => {
+                self.clock.set(time);
+                Input::Action(Some(time), action)
+            }
+            None => Input::Closed,
+        }
+    }
+}
+
+/// Runs a recording by applying the actions of an [EventSource] to a [Session] until
+/// [Action::Stop] is received or the source is closed.
+///
+/// This is the common part of all recording front-ends like [crate::record]. It keeps
+/// a status line on the terminal up to date, writes the [Journal] and stops the session
+/// once its planned duration is used up.
+pub struct Recorder<C: Clock, S: EventSource> {
+    clock: C,
+    source: S,
+    options: RecordOptions,
+    name: Option<String>,
+    notes: Option<String>,
+    display: bool,
+}
+
+impl<C: Clock, S: EventSource> Recorder<C, S> {
+    pub fn new(clock: C, source: S, options: RecordOptions) -> Self {
+        Self {
+            clock,
+            source,
+            options,
+            name: None,
+            notes: None,
+            display: true,
+        }
+    }
+
+    pub fn name(mut self, name: Option<String>) -> Self {
+        self.name = name;
+        self
+    }
+
+    pub fn notes(mut self, notes: Option<String>) -> Self {
+        self.notes = notes;
+        self
+    }
+
+    /// Whether to show the status line while recording. It is only shown on terminals.
+    pub fn display(mut self, display: bool) -> Self {
+        self.display = display;
+        self
+    }
+
+    /// Record until the session is stopped.
+    pub fn run(mut self) -> Uhms {
+        let options = &self.options;
+        let start = options.start.unwrap_or_else(|| self.clock.now());
+        let mut session = Session::new(start)
+            .planned(options.duration)
+            .precision(options.precision);
+        let terminal = console::Term::stdout();
+        let display = self.display && terminal.is_term();
+
+        let mut journal = match &options.journal {
+            Some(path) => {
+                match Journal::create(
+                    path,
+                    start,
+                    self.name.clone(),
+                    self.notes.clone(),
+                    options.duration,
+                    options.precision,
+                ) {
+                    Ok(journal) => Some(journal),
+                    Err(e) => {
+                        println!(
+                            "Cannot create journal {}, recording without it: {}",
+                            path, e
+                        );
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        if let Some(speaker) = options.speakers.first() {
+            let action = Action::Speaker {
+                name: speaker.clone(),
+            };
+            if let Some(journal) = &mut journal {
+                let _ = journal.append(start, &action);
+            }
+            session.apply(start, &action);
+        }
+
+        let end = loop {
+            let (now, action) = match self.source.next(options.refresh) {
+                Input::Action(time, action) => {
+                    (time.unwrap_or_else(|| self.clock.now()), Some(action))
+                }
+                Input::Idle => (self.clock.now(), None),
+                Input::Closed => (self.clock.now(), Some(Action::Stop)),
+            };
+
+            if options.is_over(&session, now) {
+                // stop exactly at the planned end
+                let end = now - (session.elapsed(now) - options.duration.unwrap_or_default());
+                if let Some(journal) = &mut journal {
+                    let _ = journal.append(end, &Action::Stop);
+                }
+                #[allow(unused_must_use)]
+                if display {
+                    terminal.clear_line();
+                    terminal.write_str(&session.status(end));
+                    terminal.write_line("");
+                }
+                if action != Some(Action::Stop) {
+                    println!("Time is up, the recording was stopped.");
+                    self.source.finish();
+                }
+                break end;
+            }
+
+            if let Some(action) = action {
+                // resolve toggles so that the journal contains what actually happened
+                let action = match action {
+                    Action::TogglePause if session.is_paused() => Action::Resume,
+                    Action::TogglePause => Action::Pause,
+                    action => action,
+                };
+                if let Some(journal) = &mut journal {
+                    let _ = journal.append(now, &action);
+                }
+                if action == Action::Stop {
+                    break now;
+                }
+                session.apply(now, &action);
+            }
+
+            if !display {
+                continue;
+            }
+
+            let mut status = session.status(now);
+            if let Some(duration) = options.duration {
+                status = format!("{} | {}", session.remaining(now, duration), status);
+            }
+
+            #[allow(unused_must_use)]
+            {
+                terminal.clear_line();
+                terminal.write_str(&status);
+            }
+        };
+
+        session.finish(end, self.name, self.notes)
+    }
+}
+
+/// A single uhm recorded during a [Session].
+#[derive(Debug, Clone)]
+struct Event {
+    time: DateTime<Utc>,
+    category: Option<String>,
+}
+
+/// The state of a running recording.
+///
+/// A session collects uhms and pauses as they happen and turns them into a [Uhms] once
+/// the recording is finished. Uhms are kept as absolute timestamps until then, so undoing
+/// one merges its offset into the following one.
+#[derive(Debug, Clone)]
+pub struct Session {
+    start: DateTime<Utc>,
+    events: Vec<Event>,
+    undone: Vec<Event>,
+    pauses: Vec<Span>,
+    paused_since: Option<DateTime<Utc>>,
+    markers: Vec<Marker>,
+    turns: Vec<Turn>,
+    planned: Option<TimeDelta>,
+    precision: Precision,
+}
+
+impl Session {
+    /// Start a new session at the given time.
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            events: Vec::new(),
+            undone: Vec::new(),
+            pauses: Vec::new(),
+            paused_since: None,
+            markers: Vec::new(),
+            turns: Vec::new(),
+            planned: None,
+            precision: Precision::default(),
+        }
+    }
+
+    /// Set the planned duration of the session.
+    pub fn planned(mut self, planned: Option<TimeDelta>) -> Self {
+        self.planned = planned;
+        self
+    }
+
+    /// Set the precision in which the times of the uhms are stored.
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// The time at which the session started.
+    pub fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    /// Apply an action which happened at the given time. [Action::Stop] has no effect,
+    /// use [Self::finish] instead.
+    pub fn apply(&mut self, time: DateTime<Utc>, action: &Action) {
+        match action {
+            Action::Uhm { category } => {
+                self.uhm(time, category.clone());
+            }
+            Action::Undo => {
+                self.undo();
+            }
+            Action::Redo => {
+                self.redo();
+            }
+            Action::Pause => self.pause(time),
+            Action::Resume => self.resume(time),
+            Action::TogglePause => self.toggle_pause(time),
+            Action::Marker { label } => self.marker(time, label.clone()),
+            Action::Speaker { name } => self.speaker(time, name.clone()),
+            Action::Stop => {}
+        }
+    }
+
+    /// The number of uhms recorded so far.
+    pub fn count(&self) -> usize {
+        self.events.len()
+    }
+
+    /// The number of sections started so far. A session always has at least one section.
+    pub fn sections(&self) -> usize {
+        self.markers.len() + 1
+    }
+
+    /// Whether the session is currently paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    /// Record a uhm at the given time. Uhms are ignored while the session is paused.
+    /// Recording a uhm discards all uhms which could be restored by [Self::redo].
+    ///
+    /// Returns whether the uhm was recorded.
+    pub fn uhm(&mut self, time: DateTime<Utc>, category: Option<String>) -> bool {
+        if self.is_paused() {
+            return false;
+        }
+
+        self.events.push(Event { time, category });
+        self.undone.clear();
+        true
+    }
+
+    /// Remove the last recorded uhm. It can be restored with [Self::redo].
+    ///
+    /// Returns whether there was a uhm to remove.
+    pub fn undo(&mut self) -> bool {
+        match self.events.pop() {
+            Some(event) => {
+                self.undone.push(event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Restore the last uhm removed by [Self::undo] at its original time.
+    ///
+    /// Returns whether there was a uhm to restore.
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(event) => {
+                self.events.push(event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pause the session at the given time. Does nothing if it is already paused.
+    pub fn pause(&mut self, time: DateTime<Utc>) {
+        if self.paused_since.is_none() {
+            self.paused_since = Some(time);
+        }
+    }
+
+    /// Resume the session at the given time. Does nothing if it is not paused.
+    pub fn resume(&mut self, time: DateTime<Utc>) {
+        if let Some(since) = self.paused_since.take() {
+            self.pauses.push(Span {
+                start: (since - self.start).num_milliseconds(),
+                end: (time - self.start).num_milliseconds(),
+            });
+        }
+    }
+
+    /// Pause the session if it is running, resume it otherwise.
+    pub fn toggle_pause(&mut self, time: DateTime<Utc>) {
+        if self.is_paused() {
+            self.resume(time);
+        } else {
+            self.pause(time);
+        }
+    }
+
+    /// Calculate the time spent paused between `from` and `to`, including a pause which
+    /// is still active.
+    fn paused_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> TimeDelta {
+        let from = (from - self.start).num_milliseconds();
+        let to = (to - self.start).num_milliseconds();
+        let mut paused: i64 = self.pauses.iter().map(|p| p.overlap(from, to)).sum();
+        if let Some(since) = self.paused_since {
+            let since = (since - self.start).num_milliseconds();
+            paused += Span {
+                start: since,
+                end: to,
+            }
+            .overlap(from, to);
+        }
+        TimeDelta::milliseconds(paused)
+    }
+
+    /// Calculate the time elapsed since the start of the session without paused time.
+    pub fn elapsed(&self, now: DateTime<Utc>) -> TimeDelta {
+        (now - self.start) - self.paused_between(self.start, now)
+    }
+
+    /// Calculate the average number of uhms per minute since the start of the session.
+    pub fn per_minute(&self, now: DateTime<Utc>) -> f64 {
+        crate::stats::per_minute(self.count(), &self.elapsed(now))
+    }
+
+    /// Calculate the number of uhms per minute during the last `window` of unpaused time.
+    pub fn rolling_per_minute(&self, now: DateTime<Utc>, window: TimeDelta) -> f64 {
+        let from = (now - window).max(self.start);
+        let count = self.events.iter().filter(|e| e.time >= from).count();
+        let active = (now - from) - self.paused_between(from, now);
+        crate::stats::per_minute(count, &active)
+    }
+
+    /// Calculate the time since the last recorded uhm, if there is one.
+    pub fn since_last(&self, now: DateTime<Utc>) -> Option<TimeDelta> {
+        self.events.last().map(|e| now - e.time)
+    }
+
+    /// Format the time remaining until `planned` has passed. Is highlighted shortly
+    /// before the end and once it is exceeded.
+    pub fn remaining(&self, now: DateTime<Utc>, planned: TimeDelta) -> String {
+        let remaining = planned - self.elapsed(now);
+        let (min, sec) = crate::stats::min_sec(&remaining.abs());
+
+        if remaining < TimeDelta::zero() {
+            console::style(format!("+{}:{:02.0} over", min, sec))
+                .red()
+                .bold()
+                .to_string()
+        } else if remaining <= (planned / 10).min(TimeDelta::seconds(60)) {
+            console::style(format!("{}:{:02.0} left", min, sec))
+                .yellow()
+                .bold()
+                .to_string()
+        } else {
+            format!("{}:{:02.0} left", min, sec)
+        }
+    }
+
+    /// Format a single status line summarizing the session at the given time.
+    pub fn status(&self, now: DateTime<Utc>) -> String {
+        let (min, sec) = crate::stats::min_sec(&self.elapsed(now));
+        let rate = if self.count() > 0 {
+            self.per_minute(now)
+        } else {
+            0.
+        };
+        let rolling = self.rolling_per_minute(now, TimeDelta::seconds(60));
+        let last = match self.since_last(now) {
+            Some(since) => format!("{}s ago", since.num_seconds()),
+            None => "never".to_string(),
+        };
+
+        let mut status = format!(
+            "{}:{:02.0} | {} uhm | {:.2} uhm/min | last 60s {:.2} uhm/min | last uhm {} | section {}",
+            min,
+            sec,
+            self.count(),
+            rate,
+            if rolling.is_finite() { rolling } else { 0. },
+            last,
+            self.sections(),
+        );
+        if let Some(speaker) = self.current_speaker() {
+            status.push_str(&format!(" | {} speaking", speaker));
+        }
+        if self.is_paused() {
+            status.push_str(" | paused");
+        }
+        status
+    }
+
+    /// Start a new section at the given time. Markers without a label can still be
+    /// labelled once the session is finished.
+    pub fn marker(&mut self, time: DateTime<Utc>, label: Option<String>) {
+        self.markers.push(Marker {
+            offset: self.precision.from_delta(time - self.start),
+            label,
+        });
+    }
+
+    /// Hand over to another speaker at the given time. Does nothing if the speaker is
+    /// already talking.
+    pub fn speaker(&mut self, time: DateTime<Utc>, speaker: String) {
+        if self.current_speaker() == Some(&speaker) {
+            return;
+        }
+        self.turns.push(Turn {
+            offset: self.precision.from_delta(time - self.start),
+            speaker,
+        });
+    }
+
+    /// The speaker who is currently talking, if any.
+    pub fn current_speaker(&self) -> Option<&String> {
+        self.turns.last().map(|turn| &turn.speaker)
+    }
+
+    /// Finish the session at the given time. A pause which is still active ends at `end`.
+    pub fn finish(
+        mut self,
+        end: DateTime<Utc>,
+        name: Option<String>,
+        notes: Option<String>,
+    ) -> Uhms {
+        // the session cannot end before its last uhm
+        let end = match self.events.last() {
+            Some(event) => end.max(event.time),
+            None => end,
+        };
+        self.resume(end);
+
+        let times: Vec<DateTime<Utc>> = self.events.iter().map(|e| e.time).collect();
+        let mut categories: Vec<Option<String>> =
+            self.events.into_iter().map(|e| e.category).collect();
+
+        // only keep categories if at least one of them is set
+        if categories.iter().all(|c| c.is_none()) {
+            categories.clear();
+        }
+
+        let mut uhm = Uhms {
+            id: String::new(),
+            start: self.start,
+            end,
+            data: Vec::new(),
+            times: None,
+            categories,
+            pauses: self.pauses.into_iter().filter(|p| !p.is_empty()).collect(),
+            markers: self.markers,
+            turns: self.turns,
+            planned: self.planned.map(|p| p.num_milliseconds()),
+            name,
+            notes,
+            meta: Default::default(),
+        };
+        uhm.set_event_times(&times, self.precision);
+        uhm
+    }
+}
+
+/// The first line of a [Journal] file.
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalHeader {
+    /// The start of the recording since the unix epoch in [Self::precision].
+    start: i64,
+    name: Option<String>,
+    notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    planned: Option<i64>,
+    /// The unit of [Self::start] and of the offsets of all actions.
+    #[serde(default)]
+    precision: Precision,
+}
+
+/// Every line after the first one in a [Journal] file.
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalEntry {
+    /// The time of the action relative to [JournalHeader::start] in
+    /// [JournalHeader::precision].
+    offset: i64,
+    #[serde(flatten)]
+    action: Action,
+}
+
+/// A sidecar file to which every [Action] of a running recording is appended as soon as
+/// it happens. If the recording is interrupted, it can be rebuilt with [Journal::recover].
+///
+/// The journal is a JSON Lines file. The first line contains the start time, name, notes
+/// and planned duration of the recording, every following line contains an action.
+pub struct Journal {
+    file: std::fs::File,
+    start: DateTime<Utc>,
+    precision: Precision,
+}
+
+impl Journal {
+    /// The journal used while recording to the given data file.
+    pub fn path_for(data_file: &str) -> String {
+        format!("{}.journal", data_file)
+    }
+
+    /// Create a new journal for a recording starting at `start`, which keeps all times in
+    /// the given precision. An existing file is overwritten.
+    pub fn create(
+        path: &str,
+        start: DateTime<Utc>,
+        name: Option<String>,
+        notes: Option<String>,
+        planned: Option<TimeDelta>,
+        precision: Precision,
+    ) -> Result<Self, std::io::Error> {
+        let file = std::fs::File::create(path)?;
+        let mut journal = Self {
+            file,
+            start,
+            precision,
+        };
+        let header = JournalHeader {
+            start: precision.from_delta(start - DateTime::UNIX_EPOCH),
+            name,
+            notes,
+            planned: planned.map(|p| p.num_milliseconds()),
+            precision,
+        };
+        journal.write_line(&header)?;
+        Ok(journal)
+    }
+
+    /// Append an action which happened at the given time.
+    pub fn append(&mut self, time: DateTime<Utc>, action: &Action) -> Result<(), std::io::Error> {
+        let entry = JournalEntry {
+            offset: self.precision.from_delta(time - self.start),
+            action: action.clone(),
+        };
+        self.write_line(&entry)
+    }
+
+    fn write_line<T: Serialize>(&mut self, value: &T) -> Result<(), std::io::Error> {
+        let mut line = serde_json::to_string(value)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()
+    }
+
+    /// Rebuild a recording from a journal by replaying all of its actions. The recording
+    /// ends with the last action in the journal. A truncated last line is ignored.
+    pub fn recover(path: &str) -> Result<Uhms, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return Err(format!("Cannot read journal {}: {}", path, e)),
+        };
+        let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+
+        let header: JournalHeader = match lines.next().map(serde_json::from_str) {
+            Some(Ok(header)) => header,
+            Some(Err(e)) => return Err(format!("Cannot parse journal {}: {}", path, e)),
+            None => return Err(format!("Journal {} is empty", path)),
+        };
+        let Some(start) =
+            DateTime::UNIX_EPOCH.checked_add_signed(header.precision.to_delta(header.start))
+        else {
+            return Err(format!("Journal {} has an invalid start time", path));
+        };
+
+        let mut session = Session::new(start)
+            .planned(header.planned.map(TimeDelta::milliseconds))
+            .precision(header.precision);
+        let mut end = start;
+        for line in lines {
+            // the last line might have been cut off while writing
+            let Ok(entry) = serde_json::from_str::<JournalEntry>(line) else {
+                break;
+            };
+            end = start + header.precision.to_delta(entry.offset);
+            session.apply(end, &entry.action);
+            if entry.action == Action::Stop {
+                break;
+            }
+        }
+
+        Ok(session.finish(end, header.name, header.notes))
+    }
+}
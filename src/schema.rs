@@ -0,0 +1,274 @@
+//! The versioned format of data files.
+//!
+//! Data files contain a [Document], a JSON object with the schema version and all
+//! recordings:
+//!
+//! ```json
+//! { "version": 2, "recordings": [ ... ] }
+//! ```
+//!
+//! Alternatively, data files are stored as JSON Lines, see [Format::Lines]. The first line
+//! contains the schema version, every following line a single recording:
+//!
+//! ```json
+//! {"version":5}
+//! {"id":"0123456789ab","start":"2025-01-01 10:00:00.000 +0000", ...}
+//! ```
+//!
+//! Files written by older versions are migrated when they are read, see [parse]. Version 1
+//! is the legacy format, a bare JSON array of recordings. Version 2 introduced the document,
+//! version 3 a unique [Uhms::id] for every recording, version 4 [Uhms::meta] and version 5
+//! [Uhms::times] as well as fractions of a second in [Uhms::start] and [Uhms::end], written in
+//! the precision of the times.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Uhms;
+
+/// The current schema version, which is used for all written files.
+pub const VERSION: u64 = 5;
+
+/// The contents of a data file.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Document {
+    /// The schema version of the document.
+    pub version: u64,
+    pub recordings: Vec<Uhms>,
+}
+
+impl Document {
+    /// Create a document of the current version.
+    pub fn new(recordings: Vec<Uhms>) -> Self {
+        Self {
+            version: VERSION,
+            recordings,
+        }
+    }
+}
+
+/// The layout of a data file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// A single JSON document, see [Document].
+    Json,
+    /// JSON Lines with a header line containing the version and one recording per line.
+    /// New recordings can be appended without rewriting the file.
+    #[value(name = "jsonl")]
+    Lines,
+}
+
+impl Format {
+    /// Detect the format of the contents of a data file from its first line.
+    pub fn detect(content: &str) -> Self {
+        let first = content
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default();
+        match serde_json::from_str::<Value>(first) {
+            Ok(Value::Object(header))
+                if header.contains_key("version") && !header.contains_key("recordings") =>
+            {
+                Self::Lines
+            }
+            _ => Self::Json,
+        }
+    }
+
+    /// Guess the format from the extension of a file. Defaults to [Format::Json].
+    pub fn from_path(path: &str) -> Self {
+        match std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+        {
+            Some(e) if e.eq_ignore_ascii_case("jsonl") || e.eq_ignore_ascii_case("ndjson") => {
+                Self::Lines
+            }
+            _ => Self::Json,
+        }
+    }
+
+    /// Format recordings as a data file of the current version in this format.
+    pub fn to_string(self, recordings: &[Uhms]) -> Result<String, serde_json::Error> {
+        match self {
+            Self::Json => to_string(recordings),
+            Self::Lines => to_lines(recordings),
+        }
+    }
+}
+
+/// Turns a document of one version into one of the next version.
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// Migrations from each version to the next one, starting with version 1.
+const MIGRATIONS: &[Migration] = &[wrap_array, bump_version, bump_version, bump_version];
+
+/// Version 1 to 2: wrap the bare array of recordings into a document.
+fn wrap_array(value: Value) -> Result<Value, String> {
+    let mut document = serde_json::Map::new();
+    document.insert("version".to_string(), Value::from(2));
+    document.insert("recordings".to_string(), value);
+    Ok(Value::Object(document))
+}
+
+/// Migrations which only add optional fields, which are filled in after parsing.
+fn bump_version(mut value: Value) -> Result<Value, String> {
+    if let Some(version) = value.get_mut("version") {
+        *version = Value::from(version.as_u64().unwrap_or_default() + 1);
+    }
+    Ok(value)
+}
+
+/// Detect the schema version of a parsed data file.
+pub fn version(value: &Value) -> Result<u64, String> {
+    match value {
+        Value::Array(_) => Ok(1),
+        Value::Object(object) => match object.get("version").and_then(Value::as_u64) {
+            Some(version) if version >= 1 => Ok(version),
+            _ => Err("missing or invalid schema version".to_string()),
+        },
+        _ => Err("expected a document or a list of recordings".to_string()),
+    }
+}
+
+/// Bring a parsed data file of any version up to [VERSION].
+pub fn migrate(mut value: Value) -> Result<Value, String> {
+    let version = version(&value)?;
+    if version > VERSION {
+        return Err(format!(
+            "schema version {} is newer than the supported version {}, please update uhm",
+            version, VERSION
+        ));
+    }
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        value = migration(value)?;
+    }
+    Ok(value)
+}
+
+/// Parse a data file of any version in either [Format]. Returns the migrated document
+/// together with the version the file was written in.
+pub fn parse(content: &str) -> Result<(Document, u64), String> {
+    let value = match Format::detect(content) {
+        Format::Json => serde_json::from_str(content).map_err(|e| e.to_string())?,
+        Format::Lines => parse_lines(content)?,
+    };
+    from_value(value)
+}
+
+/// Read a parsed data file of any version, like [parse].
+pub fn from_value(value: Value) -> Result<(Document, u64), String> {
+    let version = version(&value)?;
+    let mut document: Document =
+        serde_json::from_value(migrate(value)?).map_err(|e| e.to_string())?;
+    assign_ids(&mut document.recordings);
+    Ok((document, version))
+}
+
+/// Read JSON Lines into the value of an equivalent document, so they are migrated the
+/// same way.
+fn parse_lines(content: &str) -> Result<Value, String> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str::<Value>(line).map_err(|e| format!("line {}: {}", i + 1, e))
+        });
+
+    let mut document = match lines.next() {
+        Some(Ok(Value::Object(header))) => header,
+        Some(Err(e)) => return Err(e),
+        _ => return Err("missing header line".to_string()),
+    };
+    let recordings = lines.collect::<Result<Vec<Value>, String>>()?;
+    document.insert("recordings".to_string(), Value::Array(recordings));
+    Ok(Value::Object(document))
+}
+
+/// Format recordings as a document of the current version.
+pub fn to_string(recordings: &[Uhms]) -> Result<String, serde_json::Error> {
+    #[derive(Serialize)]
+    struct DocumentRef<'a> {
+        version: u64,
+        recordings: &'a [Uhms],
+    }
+
+    serde_json::to_string_pretty(&DocumentRef {
+        version: VERSION,
+        recordings,
+    })
+}
+
+/// Format recordings as JSON Lines of the current version, see [Format::Lines].
+pub fn to_lines(recordings: &[Uhms]) -> Result<String, serde_json::Error> {
+    let mut lines = header();
+    for recording in recordings {
+        lines.push_str(&to_line(recording)?);
+    }
+    Ok(lines)
+}
+
+/// The header line of JSON Lines of the current version, including the line break.
+pub fn header() -> String {
+    format!("{{\"version\":{}}}\n", VERSION)
+}
+
+/// Format a single recording as a line of JSON Lines, including the line break.
+pub fn to_line(recording: &Uhms) -> Result<String, serde_json::Error> {
+    serde_json::to_string(recording).map(|line| line + "\n")
+}
+
+/// Give every recording without an [Uhms::id] a new one which is unique among all
+/// `recordings`. IDs are derived from the content of a recording, so backfilling the same
+/// file twice yields the same IDs.
+pub fn assign_ids(recordings: &mut [Uhms]) {
+    let mut taken: Vec<String> = recordings
+        .iter()
+        .map(|r| r.id.clone())
+        .filter(|id| !id.is_empty())
+        .collect();
+
+    for recording in recordings.iter_mut().filter(|r| r.id.is_empty()) {
+        assign_id(recording, &taken);
+        taken.push(recording.id.clone());
+    }
+}
+
+/// Give a recording without an [Uhms::id] a new one which is not in `taken`.
+pub fn assign_id(recording: &mut Uhms, taken: &[String]) {
+    if !recording.id.is_empty() {
+        return;
+    }
+    let mut salt = 0;
+    recording.id = loop {
+        let id = generate_id(recording, salt);
+        if !taken.contains(&id) {
+            break id;
+        }
+        salt += 1;
+    };
+}
+
+/// Hash the content of a recording into a 12 digit hex ID.
+fn generate_id(recording: &Uhms, salt: u64) -> String {
+    // FNV-1a, which unlike the hashers of std is stable across releases
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+
+    feed(&recording.start.timestamp_millis().to_le_bytes());
+    feed(&recording.end.timestamp_millis().to_le_bytes());
+    for offset in &recording.data {
+        feed(&offset.to_le_bytes());
+    }
+    feed(recording.name.as_deref().unwrap_or_default().as_bytes());
+    feed(recording.notes.as_deref().unwrap_or_default().as_bytes());
+    feed(&salt.to_le_bytes());
+
+    format!("{:012x}", hash >> 16)
+}
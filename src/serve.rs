@@ -0,0 +1,351 @@
+//! Record uhms by tapping on a web page, e.g. on a phone.
+//!
+//! [record] starts a small HTTP server which serves a tap page on `/`. Every button of
+//! the page sends a `POST` request, which is timestamped by the server as soon as it
+//! arrives and fed into a [Recorder]. The server only answers plain HTTP/1.1 and
+//! handles one request per connection, each on its own thread, so any HTTP client can
+//! drive a recording and an idle connection does not hold up the others:
+//!
+//! ```sh
+//! curl -X POST http://127.0.0.1:8765/uhm?category=ah
+//! curl -X POST http://127.0.0.1:8765/stop
+//! ```
+//!
+//! | Route                   | Action                  |
+//! |-------------------------|-------------------------|
+//! | `POST /uhm`             | [Action::Uhm]           |
+//! | `POST /uhm?category=ah` | [Action::Uhm] with category |
+//! | `POST /undo`            | [Action::Undo]          |
+//! | `POST /redo`            | [Action::Redo]          |
+//! | `POST /pause`           | [Action::TogglePause]   |
+//! | `POST /marker`          | [Action::Marker]        |
+//! | `POST /marker?label=Q`  | [Action::Marker] with label |
+//! | `POST /speaker?name=A`  | [Action::Speaker]       |
+//! | `POST /stop`            | [Action::Stop]          |
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    Uhms,
+    record::{Action, RecordOptions, Recorder, SystemClock},
+};
+
+type Error = std::io::Error;
+
+/// How long a connection may take to send its request before it is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The largest request body accepted. Taps do not need a body at all.
+const MAX_BODY: usize = 4096;
+
+/// The most bytes read from a connection, including the request line and headers.
+const MAX_REQUEST: u64 = 16 * 1024;
+
+/// Record a session driven by requests to an HTTP server listening on `addr`, e.g.
+/// `127.0.0.1:8765` or `0.0.0.0:8765` to accept taps from the local network.
+///
+/// Blocks until the recording is stopped. Fails if the server cannot be started.
+pub fn record(
+    addr: &str,
+    name: Option<String>,
+    notes: Option<String>,
+    options: &RecordOptions,
+) -> Result<Uhms, Error> {
+    let listener = TcpListener::bind(addr)?;
+    Ok(record_on(listener, name, notes, options))
+}
+
+/// Like [record], but serves on a listener which is already bound, e.g. to port 0.
+pub fn record_on(
+    listener: TcpListener,
+    name: Option<String>,
+    notes: Option<String>,
+    options: &RecordOptions,
+) -> Uhms {
+    let page: Arc<str> = tap_page(name.as_deref(), options).into();
+
+    let addr = listener.local_addr();
+    let stopped = Arc::new(AtomicBool::new(false));
+    let (sender, actions) = mpsc::channel();
+    let server = {
+        let stopped = stopped.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let sender = sender.clone();
+                let page = page.clone();
+                // a broken connection only loses its own request
+                std::thread::spawn(move || handle(stream, &page, &sender));
+            }
+        })
+    };
+
+    let uhm = Recorder::new(SystemClock, actions, options.clone())
+        .name(name)
+        .notes(notes)
+        .run();
+
+    // wake up the server with a connection of its own, so it sees that it is stopped and
+    // releases its address
+    stopped.store(true, Ordering::SeqCst);
+    if let Ok(mut addr) = addr {
+        if addr.ip().is_unspecified() {
+            addr.set_ip(Ipv4Addr::LOCALHOST.into());
+        }
+        if TcpStream::connect(addr).is_ok() {
+            let _ = server.join();
+        }
+    }
+    uhm
+}
+
+/// Answer a single request. The requested action is sent together with the time at which
+/// the request arrived before the request is answered, so actions keep the order of
+/// their requests.
+fn handle(
+    stream: TcpStream,
+    page: &str,
+    actions: &mpsc::Sender<(DateTime<Utc>, Action)>,
+) -> Result<(), Error> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST));
+
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    let time = Utc::now();
+
+    // skip headers, but remember the length of the body
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((key, value)) = header.split_once(':')
+            && key.trim().eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    if length > MAX_BODY {
+        respond(
+            &stream,
+            "413 Content Too Large",
+            "text/plain",
+            "request too large\n",
+        )?;
+        return Ok(());
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    let mut parts = request.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let action = match (method, path) {
+        ("GET", "/") => {
+            respond(&stream, "200 OK", "text/html; charset=utf-8", page)?;
+            return Ok(());
+        }
+        ("POST", "/uhm") => Action::Uhm {
+            category: query_param(query, "category").filter(|c| !c.is_empty()),
+        },
+        ("POST", "/undo") => Action::Undo,
+        ("POST", "/redo") => Action::Redo,
+        ("POST", "/pause") => Action::TogglePause,
+        ("POST", "/marker") => Action::Marker {
+            label: query_param(query, "label").filter(|l| !l.is_empty()),
+        },
+        ("POST", "/speaker") => match query_param(query, "name").filter(|n| !n.is_empty()) {
+            Some(name) => Action::Speaker { name },
+            None => {
+                respond(&stream, "400 Bad Request", "text/plain", "missing name\n")?;
+                return Ok(());
+            }
+        },
+        ("POST", "/stop") => Action::Stop,
+        _ => {
+            respond(&stream, "404 Not Found", "text/plain", "not found\n")?;
+            return Ok(());
+        }
+    };
+
+    if actions.send((time, action)).is_err() {
+        return respond(&stream, "410 Gone", "text/plain", "recording stopped\n");
+    }
+    respond(&stream, "200 OK", "text/plain", "ok\n")
+}
+
+fn respond(
+    mut stream: &TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<(), Error> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Find a parameter in a query string like `category=ah&x=y` and decode it.
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| decode(value))
+}
+
+/// Decode a percent-encoded query value.
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Percent-encode a query value.
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Render the tap page with one button per category, or a single button if there are
+/// no categories, and one button per speaker.
+fn tap_page(name: Option<&str>, options: &RecordOptions) -> String {
+    let mut categories: Vec<&String> = options.categories.values().collect();
+    categories.extend(options.bindings.categories());
+    categories.sort();
+    categories.dedup();
+
+    let mut buttons = String::new();
+    if categories.is_empty() {
+        buttons.push_str("<button class=\"tap\" data-route=\"/uhm\">uhm</button>\n");
+    }
+    for category in categories {
+        buttons.push_str(&format!(
+            "<button class=\"tap\" data-route=\"/uhm?category={}\">{}</button>\n",
+            encode(category),
+            escape(category),
+        ));
+    }
+
+    let mut speakers = String::new();
+    for speaker in &options.speakers {
+        speakers.push_str(&format!(
+            "<button data-route=\"/speaker?name={}\">{}</button>\n",
+            encode(speaker),
+            escape(speaker),
+        ));
+    }
+
+    TAP_PAGE
+        .replace("{title}", &escape(name.unwrap_or("uhm")))
+        .replace("{buttons}", &buttons)
+        .replace("{speakers}", &speakers)
+}
+
+const TAP_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+body { font-family: sans-serif; margin: 0; padding: 1em; display: flex; flex-direction: column; gap: 0.5em; height: 100vh; box-sizing: border-box; }
+button { font-size: 1.5em; padding: 0.5em; border-radius: 0.3em; border: 1px solid #888; }
+.tap { flex: 1; font-size: 2.5em; background: #1f77b4; color: white; }
+.controls { display: flex; gap: 0.5em; }
+.controls button { flex: 1; }
+#status { text-align: center; color: #555; }
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{buttons}
+<div class="controls">
+{speakers}</div>
+<div class="controls">
+<button data-route="/undo">Undo</button>
+<button data-route="/redo">Redo</button>
+<button data-route="/pause">Pause</button>
+<button data-route="/marker">Section</button>
+<button data-route="/stop">Stop</button>
+</div>
+<div id="status"></div>
+<script>
+let taps = 0;
+for (const button of document.querySelectorAll("button")) {
+  button.addEventListener("click", async () => {
+    try {
+      const response = await fetch(button.dataset.route, { method: "POST" });
+      if (!response.ok) throw new Error(response.statusText);
+      taps += 1;
+      document.getElementById("status").textContent = button.textContent + " (" + taps + " taps sent)";
+    } catch (e) {
+      document.getElementById("status").textContent = "not connected";
+    }
+  });
+}
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,287 @@
+//! Store recordings in an SQLite database, for long histories which are queried often.
+//! Only available with the `sqlite` feature.
+//!
+//! Every recording is a row of the `recordings` table, which keeps the recording as JSON
+//! like in data files, see [crate::schema]. Its name and start as well as its tags are
+//! copied into indexed columns, so [Database::query] only parses matching recordings. The
+//! schema version of the JSON is kept in the `user_version` of the database.
+//!
+//! Recordings are read and changed through the [Store] trait.
+
+use rusqlite::{Connection, params, params_from_iter, types::Value as SqlValue};
+use serde_json::Value;
+
+use crate::{
+    Uhms,
+    query::Query,
+    schema::{self, Document, VERSION},
+    store::Store,
+};
+
+const TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS recordings (
+        position INTEGER PRIMARY KEY,
+        id TEXT NOT NULL UNIQUE,
+        name TEXT,
+        start INTEGER NOT NULL,
+        json TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS recordings_name ON recordings (name);
+    CREATE INDEX IF NOT EXISTS recordings_start ON recordings (start);
+    CREATE TABLE IF NOT EXISTS tags (
+        tag TEXT NOT NULL,
+        recording INTEGER NOT NULL REFERENCES recordings (position) ON DELETE CASCADE,
+        PRIMARY KEY (tag, recording)
+    );
+";
+
+/// A database of recordings.
+pub struct Database {
+    connection: Connection,
+    backups: usize,
+    /// Whether the database was created when it was opened, so there is nothing to back up.
+    created: bool,
+}
+
+impl Database {
+    /// Open a database, creating it if it does not exist.
+    pub fn open(path: &str) -> Result<Self, String> {
+        let created = !std::path::Path::new(path).is_file();
+        let connection = Connection::open(path).map_err(|e| e.to_string())?;
+        connection
+            .execute_batch(&format!("PRAGMA foreign_keys = ON; {}", TABLES))
+            .map_err(|e| e.to_string())?;
+
+        let database = Self {
+            connection,
+            backups: 0,
+            created,
+        };
+        if database.version()? == 0 {
+            database
+                .connection
+                .execute_batch(&format!("PRAGMA user_version = {}", VERSION))
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(database)
+    }
+
+    /// Keep up to this many backups of the database. Only the first change except for
+    /// inserts backs it up, see [crate::io::rotate_backups].
+    pub fn backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    fn back_up(&mut self) -> Result<(), String> {
+        if let Some(path) = self
+            .connection
+            .path()
+            .filter(|path| !path.is_empty() && !self.created)
+        {
+            crate::io::rotate_backups(path, self.backups).map_err(|e| e.to_string())?;
+        }
+        self.backups = 0;
+        Ok(())
+    }
+
+    /// Read all recordings. Returns them as a migrated document together with the version
+    /// they were written in, like [schema::parse].
+    pub fn load(&self) -> Result<(Document, u64), String> {
+        self.select("", Vec::new())
+    }
+
+    /// Read the recordings matching a `WHERE` clause in their order.
+    fn select(&self, filter: &str, values: Vec<SqlValue>) -> Result<(Document, u64), String> {
+        let sql = format!("SELECT json FROM recordings {} ORDER BY position", filter);
+        let mut statement = self.connection.prepare(&sql).map_err(|e| e.to_string())?;
+        let recordings = statement
+            .query_map(params_from_iter(values), |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?
+            .map(|json| {
+                let json = json.map_err(|e| e.to_string())?;
+                serde_json::from_str::<Value>(&json).map_err(|e| e.to_string())
+            })
+            .collect::<Result<Vec<Value>, String>>()?;
+
+        // recordings of older versions are migrated like data files
+        let mut document = serde_json::Map::new();
+        document.insert("version".to_string(), Value::from(self.version()?));
+        document.insert("recordings".to_string(), Value::Array(recordings));
+        schema::from_value(Value::Object(document))
+    }
+}
+
+impl Store for Database {
+    fn list(&self) -> Result<Vec<Uhms>, String> {
+        self.load().map(|(document, _)| document.recordings)
+    }
+
+    fn query(&self, query: &Query) -> Result<Vec<Uhms>, String> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(name) = &query.name {
+            conditions.push("name = ?");
+            values.push(SqlValue::Text(name.clone()));
+        }
+        if let Some(since) = query.since {
+            conditions.push("start >= ?");
+            values.push(SqlValue::Integer(since.timestamp_millis()));
+        }
+        if let Some(before) = query.before {
+            conditions.push("start < ?");
+            values.push(SqlValue::Integer(before.timestamp_millis()));
+        }
+        for tag in &query.tags {
+            conditions.push("position IN (SELECT recording FROM tags WHERE tag = ?)");
+            values.push(SqlValue::Text(tag.clone()));
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        self.select(&filter, values)
+            .map(|(document, _)| document.recordings)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Uhms>, String> {
+        let (document, _) = self.select("WHERE id = ?", vec![SqlValue::Text(id.to_string())])?;
+        Ok(document.recordings.into_iter().next())
+    }
+
+    fn insert(&mut self, mut recording: Uhms) -> Result<String, String> {
+        let version = self.version()?;
+        if version != VERSION {
+            return Err(format!(
+                "cannot add to a database of schema version {}, please migrate it",
+                version
+            ));
+        }
+
+        let transaction = self.connection.transaction().map_err(|e| e.to_string())?;
+        let taken = {
+            let mut statement = transaction
+                .prepare("SELECT id FROM recordings")
+                .map_err(|e| e.to_string())?;
+            statement
+                .query_map([], |row| row.get::<_, String>(0))
+                .and_then(|ids| ids.collect::<Result<Vec<String>, _>>())
+                .map_err(|e| e.to_string())?
+        };
+        if taken.contains(&recording.id) {
+            return Err(format!(
+                "a recording with ID {} already exists",
+                recording.id
+            ));
+        }
+        schema::assign_id(&mut recording, &taken);
+        insert(&transaction, &recording)?;
+        transaction.commit().map_err(|e| e.to_string())?;
+        Ok(recording.id)
+    }
+
+    fn update(&mut self, recording: Uhms) -> Result<(), String> {
+        if self.get(&recording.id)?.is_none() {
+            return Err(format!("no recording with ID {}", recording.id));
+        }
+        self.back_up()?;
+        let json = serde_json::to_string(&recording).map_err(|e| e.to_string())?;
+        let transaction = self.connection.transaction().map_err(|e| e.to_string())?;
+        let changed = transaction
+            .execute(
+                "UPDATE recordings SET name = ?, start = ?, json = ? WHERE id = ?",
+                params![
+                    recording.name,
+                    recording.start.timestamp_millis(),
+                    json,
+                    recording.id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err(format!("no recording with ID {}", recording.id));
+        }
+
+        let position: i64 = transaction
+            .query_row(
+                "SELECT position FROM recordings WHERE id = ?",
+                params![recording.id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        transaction
+            .execute("DELETE FROM tags WHERE recording = ?", params![position])
+            .map_err(|e| e.to_string())?;
+        insert_tags(&transaction, position, &recording)?;
+        transaction.commit().map_err(|e| e.to_string())
+    }
+
+    fn delete(&mut self, id: &str) -> Result<Option<Uhms>, String> {
+        let Some(recording) = self.get(id)? else {
+            return Ok(None);
+        };
+        self.back_up()?;
+        self.connection
+            .execute("DELETE FROM recordings WHERE id = ?", params![id])
+            .map_err(|e| e.to_string())?;
+        Ok(Some(recording))
+    }
+
+    /// Replace all recordings of the database in a single transaction.
+    fn replace(&mut self, mut recordings: Vec<Uhms>) -> Result<(), String> {
+        schema::assign_ids(&mut recordings);
+        self.back_up()?;
+        let transaction = self.connection.transaction().map_err(|e| e.to_string())?;
+        transaction
+            .execute_batch("DELETE FROM tags; DELETE FROM recordings;")
+            .map_err(|e| e.to_string())?;
+        for recording in &recordings {
+            insert(&transaction, recording)?;
+        }
+        transaction
+            .execute_batch(&format!("PRAGMA user_version = {}", VERSION))
+            .map_err(|e| e.to_string())?;
+        transaction.commit().map_err(|e| e.to_string())
+    }
+
+    /// The schema version of the recordings, kept in the `user_version` of the database.
+    fn version(&self) -> Result<u64, String> {
+        self.connection
+            .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+            .map(|version| version as u64)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Insert a recording with its tags after all others.
+fn insert(connection: &Connection, recording: &Uhms) -> Result<(), String> {
+    let json = serde_json::to_string(recording).map_err(|e| e.to_string())?;
+    connection
+        .execute(
+            "INSERT INTO recordings (id, name, start, json) VALUES (?, ?, ?, ?)",
+            params![
+                recording.id,
+                recording.name,
+                recording.start.timestamp_millis(),
+                json
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+
+    insert_tags(connection, connection.last_insert_rowid(), recording)
+}
+
+/// Index the tags of the recording at the given position.
+fn insert_tags(connection: &Connection, position: i64, recording: &Uhms) -> Result<(), String> {
+    for tag in &recording.meta.tags {
+        connection
+            .execute(
+                "INSERT OR IGNORE INTO tags (tag, recording) VALUES (?, ?)",
+                params![tag, position],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
@@ -10,35 +10,125 @@ use crate::data::Uhms;
 /// If you need all of them, use [UhmStats::new].
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UhmStats {
+    /// The [Uhms::id] of the series.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
     /// Total number of uhms.
     pub count: usize,
     /// Average for duration between two uhms (in milliseconds).
     pub delay_mean: f64,
     /// Standard deviation for duration between two uhms (in milliseconds).
     pub delay_std: f64,
-    /// Number of minutes and remaining time in seconds of the duration.
+    /// Number of minutes and remaining time in seconds of the duration, excluding pauses.
     pub min_sec: (i64, f64),
-    /// The average number of uhms per minute, averaged over the whole duration.
+    /// Number of minutes and remaining time in seconds during which the recording was paused.
+    #[serde(default)]
+    pub paused_min_sec: (i64, f64),
+    /// The average number of uhms per minute, averaged over the whole duration without pauses.
+    pub per_minute: f64,
+    /// Number of minutes and remaining time in seconds of the planned duration, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planned_min_sec: Option<(i64, f64)>,
+    /// Seconds by which the presentation exceeded (positive) or fell short of (negative)
+    /// the planned duration, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overtime: Option<f64>,
+    /// Count and rate for each category. Empty if the series has no categories.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<CategoryStats>,
+    /// Count and rate for each section. Empty if the series has no markers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sections: Vec<SectionStats>,
+    /// Count and rate for each speaker. Empty if the series has no speaker turns.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub speakers: Vec<SpeakerStats>,
+}
+
+/// Stats for all uhms of a single speaker. See [by_speaker].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpeakerStats {
+    /// The name of the speaker. Is `None` for the time before the first turn.
+    pub name: Option<String>,
+    /// Number of uhms of this speaker.
+    pub count: usize,
+    /// Number of minutes and remaining time in seconds this speaker talked, excluding pauses.
+    pub min_sec: (i64, f64),
+    /// The average number of uhms per minute of this speaker's own talking time.
+    pub per_minute: f64,
+}
+
+/// Stats for all uhms inside a single section. See [by_section].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SectionStats {
+    /// The label of the marker this section starts at.
+    pub label: Option<String>,
+    /// The time at which the section starts in milliseconds relative to [Uhms::start].
+    pub start: i64,
+    /// The time at which the section ends in milliseconds relative to [Uhms::start].
+    pub end: i64,
+    /// Number of uhms inside this section.
+    pub count: usize,
+    /// The average number of uhms per minute inside this section, excluding pauses.
+    pub per_minute: f64,
+}
+
+/// Stats for all uhms of a single category. See [by_category].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryStats {
+    /// The name of the category. Is `None` for uhms recorded without a category.
+    pub name: Option<String>,
+    /// Number of uhms of this category.
+    pub count: usize,
+    /// The average number of uhms of this category per minute.
+    pub per_minute: f64,
+}
+
+/// Combined stats of all series sharing a metadata value. See [by_group].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupStats {
+    /// The metadata value of this group. Is `None` for series without a value.
+    pub name: Option<String>,
+    /// Number of series in this group.
+    pub recordings: usize,
+    /// Total number of uhms of all series in this group.
+    pub count: usize,
+    /// Number of minutes and remaining time in seconds of all series, excluding pauses.
+    pub min_sec: (i64, f64),
+    /// The average number of uhms per minute over all series, excluding pauses.
     pub per_minute: f64,
 }
 
 impl UhmStats {
     /// Calculate all stats and create a new [UhmStats] from them.
     pub fn new(uhms: &Uhms) -> Self {
-        let duration = uhms.duration();
+        let duration = uhms.active_duration();
+        let delays = uhms.active_data();
 
         let count = uhms.data.len();
-        let delay_mean = mean(&uhms.data);
-        let delay_std = std(&uhms.data);
+        let delay_mean = mean(&delays);
+        let delay_std = std(&delays);
+        let paused_min_sec = min_sec(&uhms.paused());
+        let planned_min_sec = uhms.planned.map(|p| min_sec(&TimeDelta::milliseconds(p)));
         let min_sec = min_sec(&duration);
         let per_minute = per_minute(count, &duration);
+        let overtime = uhms.overtime().map(|o| o.num_milliseconds() as f64 / 1000.);
+        let categories = by_category(uhms);
+        let sections = by_section(uhms);
+        let speakers = by_speaker(uhms);
 
         Self {
+            id: uhms.id.clone(),
             count,
             delay_mean,
             delay_std,
             min_sec,
+            paused_min_sec,
             per_minute,
+            planned_min_sec,
+            overtime,
+            categories,
+            sections,
+            speakers,
         }
     }
 }
@@ -48,13 +138,204 @@ pub fn count(uhms: &Uhms) -> usize {
     uhms.data.len()
 }
 
+/// Count the uhms of each category and calculate their rate.
+///
+/// Categories are listed in order of their first occurrence. Uhms without a category
+/// are collected last under the name `None`. If the series has no categories at all,
+/// the result is empty.
+pub fn by_category(uhms: &Uhms) -> Vec<CategoryStats> {
+    if uhms.categories.is_empty() {
+        return Vec::new();
+    }
+
+    let duration = uhms.active_duration();
+    let mut result: Vec<CategoryStats> = uhms
+        .category_names()
+        .into_iter()
+        .map(|name| CategoryStats {
+            name: Some(name.clone()),
+            count: 0,
+            per_minute: 0.,
+        })
+        .collect();
+    let mut uncategorized = 0;
+
+    for category in &uhms.categories {
+        match category {
+            Some(name) => {
+                if let Some(stats) = result.iter_mut().find(|s| s.name.as_ref() == Some(name)) {
+                    stats.count += 1;
+                }
+            }
+            None => uncategorized += 1,
+        }
+    }
+
+    if uncategorized > 0 {
+        result.push(CategoryStats {
+            name: None,
+            count: uncategorized,
+            per_minute: 0.,
+        });
+    }
+
+    for stats in &mut result {
+        stats.per_minute = per_minute(stats.count, &duration);
+    }
+
+    result
+}
+
+/// Count the uhms inside each section and calculate their rate. Uhms which occur exactly
+/// at a marker belong to the section starting there.
+///
+/// See [Uhms::sections]. If the series has no markers, the result is empty.
+pub fn by_section(uhms: &Uhms) -> Vec<SectionStats> {
+    if uhms.markers.is_empty() {
+        return Vec::new();
+    }
+
+    let offsets = uhms.offsets();
+    let sections = uhms.sections();
+    let last = sections.len() - 1;
+
+    sections
+        .into_iter()
+        .enumerate()
+        .map(|(i, (span, label))| {
+            let count = offsets
+                .iter()
+                .filter(|o| **o >= span.start && (**o < span.end || (i == last && **o == span.end)))
+                .count();
+            let active = span.len() - uhms.paused_between(span.start, span.end);
+            SectionStats {
+                label: label.cloned(),
+                start: span.start,
+                end: span.end,
+                count,
+                per_minute: per_minute(count, &TimeDelta::milliseconds(active)),
+            }
+        })
+        .collect()
+}
+
+/// Count the uhms of each speaker and calculate their rate based on the time the speaker
+/// talked, excluding pauses. Uhms which occur exactly at a turn belong to the new speaker.
+///
+/// Speakers are listed in order of their first turn. Uhms before the first turn are
+/// collected last under the name `None`. If the series has no turns, the result is empty.
+pub fn by_speaker(uhms: &Uhms) -> Vec<SpeakerStats> {
+    if uhms.turns.is_empty() {
+        return Vec::new();
+    }
+
+    let offsets = uhms.offsets();
+    let turns = uhms.speaker_turns();
+    let last = turns.len() - 1;
+
+    // (speaker, count, active milliseconds)
+    let mut totals: Vec<(Option<&String>, usize, i64)> = uhms
+        .speaker_names()
+        .into_iter()
+        .map(|name| (Some(name), 0, 0))
+        .collect();
+    totals.push((None, 0, 0));
+
+    for (i, (span, speaker)) in turns.into_iter().enumerate() {
+        let count = offsets
+            .iter()
+            .filter(|o| **o >= span.start && (**o < span.end || (i == last && **o == span.end)))
+            .count();
+        let active = span.len() - uhms.paused_between(span.start, span.end);
+        if let Some(total) = totals.iter_mut().find(|t| t.0 == speaker) {
+            total.1 += count;
+            total.2 += active;
+        }
+    }
+
+    totals
+        .into_iter()
+        .filter(|(name, count, active)| name.is_some() || *count > 0 || *active > 0)
+        .map(|(name, count, active)| {
+            let active = TimeDelta::milliseconds(active);
+            SpeakerStats {
+                name: name.cloned(),
+                count,
+                min_sec: min_sec(&active),
+                per_minute: per_minute(count, &active),
+            }
+        })
+        .collect()
+}
+
+/// Group series by the value of a metadata key, see [crate::Metadata::values], and combine
+/// their stats. A series with several tags belongs to several groups.
+///
+/// Groups are listed in order of their first occurrence. Series without a value are
+/// collected last under the name `None`. Fails for unknown keys.
+pub fn by_group(uhms: &[&Uhms], key: &str) -> Result<Vec<GroupStats>, String> {
+    // (value, recordings, count, active milliseconds)
+    let mut groups: Vec<(Option<String>, usize, usize, i64)> = Vec::new();
+
+    for uhm in uhms {
+        let Some(values) = uhm.meta.values(key) else {
+            return Err(format!(
+                "unknown metadata key '{}', expected one of {}",
+                key,
+                crate::Metadata::KEYS.join(", ")
+            ));
+        };
+        let values: Vec<Option<String>> = if values.is_empty() {
+            vec![None]
+        } else {
+            values.into_iter().map(Some).collect()
+        };
+
+        for value in values {
+            let index = match groups.iter().position(|g| g.0 == value) {
+                Some(index) => index,
+                None => {
+                    groups.push((value, 0, 0, 0));
+                    groups.len() - 1
+                }
+            };
+            let group = &mut groups[index];
+            group.1 += 1;
+            group.2 += uhm.data.len();
+            group.3 += uhm.active_duration().num_milliseconds();
+        }
+    }
+
+    // series without a value last
+    groups.sort_by_key(|g| g.0.is_none());
+
+    Ok(groups
+        .into_iter()
+        .map(|(name, recordings, count, active)| {
+            let active = TimeDelta::milliseconds(active);
+            GroupStats {
+                name,
+                recordings,
+                count,
+                min_sec: min_sec(&active),
+                per_minute: per_minute(count, &active),
+            }
+        })
+        .collect())
+}
+
 /// Calculate the average number of uhms per minute, averaged over the whole
 /// duration.
 ///
 /// The count can be retrieved from [uhm::stats::count].
-/// The duration can be retrieved from e.g. [Uhms::duration].
+/// The duration can be retrieved from e.g. [Uhms::active_duration], which excludes
+/// paused time, or [Uhms::duration]. Spans without any duration, like a section between
+/// two markers at the same time, have a rate of 0.
 pub fn per_minute(count: usize, duration: &TimeDelta) -> f64 {
     let milliseconds = duration.num_milliseconds();
+    if milliseconds <= 0 {
+        return 0.;
+    }
     (count as f64) / (milliseconds as f64 / 1000. / 60.)
 }
 
@@ -73,7 +354,7 @@ pub fn min_sec(duration: &TimeDelta) -> (i64, f64) {
 pub fn mean(items: &Vec<i64>) -> f64 {
     let mut sum = 0;
     for item in items {
-        sum = sum + *item;
+        sum += *item;
     }
     sum as f64 / items.len() as f64
 }
@@ -85,9 +366,9 @@ pub fn var(items: &Vec<i64>) -> f64 {
     let mean = mean(items);
     let mut sum = 0.;
     for item in items {
-        sum = sum + (*item as f64 + mean) * (*item as f64 + mean);
+        sum += (*item as f64 + mean) * (*item as f64 + mean);
     }
-    sum as f64 / items.len() as f64
+    sum / items.len() as f64
 }
 
 /// Calculate the standard deviation of the given series.
@@ -0,0 +1,409 @@
+//! Collections of recordings which are read and changed one recording at a time.
+//!
+//! A [Store] keeps recordings in order and gives access to them by their [Uhms::id].
+//! Recordings are kept in memory by a [MemoryStore], in a data file by a [FileStore] or,
+//! with the `sqlite` feature, in a database by [crate::sqlite::Database]. A [MultiStore]
+//! reads several stores as one, e.g. all data files of a shared folder.
+
+use crate::{
+    Uhms,
+    io::WriteTarget,
+    query::Query,
+    schema::{self, Format, VERSION},
+};
+
+/// Storage of recordings.
+pub trait Store {
+    /// All recordings in their order.
+    fn list(&self) -> Result<Vec<Uhms>, String>;
+
+    /// All recordings matching a query in their order.
+    fn query(&self, query: &Query) -> Result<Vec<Uhms>, String> {
+        let mut recordings = self.list()?;
+        recordings.retain(|uhm| query.matches(uhm));
+        Ok(recordings)
+    }
+
+    /// The recording with the given ID, if there is one.
+    fn get(&self, id: &str) -> Result<Option<Uhms>, String> {
+        Ok(self.list()?.into_iter().find(|uhm| uhm.id == id))
+    }
+
+    /// Add a recording after all others. A recording without an ID is assigned a new one.
+    /// Returns the ID of the recording.
+    fn insert(&mut self, recording: Uhms) -> Result<String, String>;
+
+    /// Replace the recording with the same ID.
+    fn update(&mut self, recording: Uhms) -> Result<(), String>;
+
+    /// Remove the recording with the given ID. Returns the removed recording, if any.
+    fn delete(&mut self, id: &str) -> Result<Option<Uhms>, String>;
+
+    /// Replace all recordings at once, e.g. after migrating them. Recordings without an ID
+    /// are assigned one.
+    fn replace(&mut self, recordings: Vec<Uhms>) -> Result<(), String>;
+
+    /// The schema version the recordings were stored in, see [crate::schema].
+    fn version(&self) -> Result<u64, String> {
+        Ok(VERSION)
+    }
+}
+
+/// Keeps recordings in memory, e.g. for tests or data read from stdin.
+#[derive(Debug, Clone)]
+pub struct MemoryStore {
+    recordings: Vec<Uhms>,
+    version: u64,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl MemoryStore {
+    /// Create a store of the current version. Recordings without an ID are assigned one.
+    pub fn new(mut recordings: Vec<Uhms>) -> Self {
+        schema::assign_ids(&mut recordings);
+        Self {
+            recordings,
+            version: VERSION,
+        }
+    }
+
+    /// Set the schema version the recordings were read in.
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// The recordings of the store in their order.
+    pub fn recordings(&self) -> &[Uhms] {
+        &self.recordings
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.recordings.iter().position(|uhm| uhm.id == id)
+    }
+}
+
+impl Store for MemoryStore {
+    fn list(&self) -> Result<Vec<Uhms>, String> {
+        Ok(self.recordings.clone())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Uhms>, String> {
+        Ok(self.position(id).map(|i| self.recordings[i].clone()))
+    }
+
+    fn insert(&mut self, mut recording: Uhms) -> Result<String, String> {
+        if self.position(&recording.id).is_some() {
+            return Err(format!(
+                "a recording with ID {} already exists",
+                recording.id
+            ));
+        }
+        let taken: Vec<String> = self.recordings.iter().map(|uhm| uhm.id.clone()).collect();
+        schema::assign_id(&mut recording, &taken);
+        let id = recording.id.clone();
+        self.recordings.push(recording);
+        Ok(id)
+    }
+
+    fn update(&mut self, recording: Uhms) -> Result<(), String> {
+        match self.position(&recording.id) {
+            Some(i) => {
+                self.recordings[i] = recording;
+                Ok(())
+            }
+            None => Err(format!("no recording with ID {}", recording.id)),
+        }
+    }
+
+    fn delete(&mut self, id: &str) -> Result<Option<Uhms>, String> {
+        Ok(self.position(id).map(|i| self.recordings.remove(i)))
+    }
+
+    fn replace(&mut self, recordings: Vec<Uhms>) -> Result<(), String> {
+        *self = Self::new(recordings);
+        Ok(())
+    }
+
+    fn version(&self) -> Result<u64, String> {
+        Ok(self.version)
+    }
+}
+
+/// Keeps recordings in a data file, either as a JSON document or as JSON Lines, see
+/// [Format]. The whole file is read when it is opened and written whenever a recording
+/// changes, except for JSON Lines, which new recordings are appended to.
+///
+/// Writes are atomic. The first write of a store keeps a backup of the previous file, see
+/// [WriteTarget::replace].
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    path: String,
+    format: Format,
+    /// The format of the file on disk, if it exists.
+    stored: Option<Format>,
+    memory: MemoryStore,
+    backups: usize,
+}
+
+impl FileStore {
+    /// Open a data file. A missing or empty file is created when it is first written. The
+    /// format of an existing file is detected from its contents, of a new one from its
+    /// extension, see [Format::from_path].
+    pub fn open(path: &str) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let (memory, stored) = if content.trim().is_empty() {
+            (MemoryStore::default(), None)
+        } else {
+            let (document, version) = schema::parse(&content)?;
+            let memory = MemoryStore::new(document.recordings).with_version(version);
+            (memory, Some(Format::detect(&content)))
+        };
+        Ok(Self {
+            path: path.to_string(),
+            format: stored.unwrap_or_else(|| Format::from_path(path)),
+            stored,
+            memory,
+            backups: 0,
+        })
+    }
+
+    /// Write the file in the given format from now on.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Keep up to this many backups of the file, see [WriteTarget::replace].
+    pub fn backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    /// The path of the data file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Write all recordings to the file.
+    fn save(&mut self) -> Result<(), String> {
+        let content = self
+            .format
+            .to_string(self.memory.recordings())
+            .map_err(|e| e.to_string())?;
+        WriteTarget::File(self.path.clone())
+            .replace(&content, self.backups)
+            .map_err(|e| e.to_string())?;
+
+        // only the first write replaces a backup
+        self.backups = 0;
+        self.stored = Some(self.format);
+        self.memory.version = VERSION;
+        Ok(())
+    }
+
+    /// Append the last recording to JSON Lines of the current version.
+    fn append(&mut self) -> Result<(), String> {
+        use std::io::{Read, Seek, SeekFrom, Write};
+
+        let Some(recording) = self.memory.recordings().last() else {
+            return Ok(());
+        };
+        let line = schema::to_line(recording).map_err(|e| e.to_string())?;
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| e.to_string())?;
+
+        // the last line may lack its line break after editing the file by hand
+        let mut last = [0];
+        let appended = file
+            .seek(SeekFrom::End(-1))
+            .and_then(|_| file.read_exact(&mut last))
+            .and_then(|_| match last[0] {
+                b'\n' => Ok(()),
+                _ => file.write_all(b"\n"),
+            })
+            .and_then(|_| file.write_all(line.as_bytes()));
+        appended.map_err(|e| e.to_string())
+    }
+}
+
+impl Store for FileStore {
+    fn list(&self) -> Result<Vec<Uhms>, String> {
+        self.memory.list()
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Uhms>, String> {
+        self.memory.get(id)
+    }
+
+    fn insert(&mut self, recording: Uhms) -> Result<String, String> {
+        let id = self.memory.insert(recording)?;
+        let appendable = self.format == Format::Lines
+            && self.stored == Some(Format::Lines)
+            && self.memory.version == VERSION;
+        if appendable {
+            self.append()?;
+        } else {
+            self.save()?;
+        }
+        Ok(id)
+    }
+
+    fn update(&mut self, recording: Uhms) -> Result<(), String> {
+        self.memory.update(recording)?;
+        self.save()
+    }
+
+    fn delete(&mut self, id: &str) -> Result<Option<Uhms>, String> {
+        let deleted = self.memory.delete(id)?;
+        if deleted.is_some() {
+            self.save()?;
+        }
+        Ok(deleted)
+    }
+
+    fn replace(&mut self, recordings: Vec<Uhms>) -> Result<(), String> {
+        self.memory.replace(recordings)?;
+        self.save()
+    }
+
+    fn version(&self) -> Result<u64, String> {
+        self.memory.version()
+    }
+}
+
+/// Reads the recordings of several named stores as one, e.g. of several data files, in
+/// the order of the stores. Every recording is changed in the store it comes from, see
+/// [Self::source]. New recordings cannot be inserted, as it is unclear where they belong.
+///
+/// If several stores contain a recording with the same ID, only the first one of them can
+/// be changed.
+#[derive(Default)]
+pub struct MultiStore {
+    stores: Vec<(String, Box<dyn Store>)>,
+}
+
+impl MultiStore {
+    /// Add a store with the name its recordings are shown with, e.g. its path.
+    pub fn with(mut self, name: &str, store: Box<dyn Store>) -> Self {
+        self.stores.push((name.to_string(), store));
+        self
+    }
+
+    /// The number of stores.
+    pub fn len(&self) -> usize {
+        self.stores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stores.is_empty()
+    }
+
+    /// Read all recordings like [Store::list], each with the name of its store.
+    pub fn list_with_sources(&self) -> Result<Vec<(String, Uhms)>, String> {
+        let mut recordings = Vec::new();
+        for (name, store) in &self.stores {
+            recordings.extend(
+                store
+                    .list()?
+                    .into_iter()
+                    .map(|recording| (name.clone(), recording)),
+            );
+        }
+        Ok(recordings)
+    }
+
+    /// Read the matching recordings like [Store::query], each with the name of its store.
+    pub fn query_with_sources(&self, query: &Query) -> Result<Vec<(String, Uhms)>, String> {
+        let mut recordings = Vec::new();
+        for (name, store) in &self.stores {
+            recordings.extend(
+                store
+                    .query(query)?
+                    .into_iter()
+                    .map(|recording| (name.clone(), recording)),
+            );
+        }
+        Ok(recordings)
+    }
+
+    fn containing(&mut self, id: &str) -> Result<Option<&mut Box<dyn Store>>, String> {
+        for (_, store) in self.stores.iter_mut() {
+            if store.get(id)?.is_some() {
+                return Ok(Some(store));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Store for MultiStore {
+    fn list(&self) -> Result<Vec<Uhms>, String> {
+        let mut recordings = Vec::new();
+        for (_, store) in &self.stores {
+            recordings.extend(store.list()?);
+        }
+        Ok(recordings)
+    }
+
+    fn query(&self, query: &Query) -> Result<Vec<Uhms>, String> {
+        let mut recordings = Vec::new();
+        for (_, store) in &self.stores {
+            recordings.extend(store.query(query)?);
+        }
+        Ok(recordings)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Uhms>, String> {
+        for (_, store) in &self.stores {
+            if let Some(recording) = store.get(id)? {
+                return Ok(Some(recording));
+            }
+        }
+        Ok(None)
+    }
+
+    fn insert(&mut self, _recording: Uhms) -> Result<String, String> {
+        Err("cannot add a recording to several stores at once".to_string())
+    }
+
+    fn update(&mut self, recording: Uhms) -> Result<(), String> {
+        match self.containing(&recording.id)? {
+            Some(store) => store.update(recording),
+            None => Err(format!("no recording with ID {}", recording.id)),
+        }
+    }
+
+    fn delete(&mut self, id: &str) -> Result<Option<Uhms>, String> {
+        match self.containing(id)? {
+            Some(store) => store.delete(id),
+            None => Ok(None),
+        }
+    }
+
+    fn replace(&mut self, _recordings: Vec<Uhms>) -> Result<(), String> {
+        Err("cannot replace the recordings of several stores at once".to_string())
+    }
+
+    /// The oldest schema version of all stores.
+    fn version(&self) -> Result<u64, String> {
+        let mut version = VERSION;
+        for (_, store) in &self.stores {
+            version = version.min(store.version()?);
+        }
+        Ok(version)
+    }
+}
@@ -0,0 +1,145 @@
+//! Record uhms from a stream of text lines, e.g. from stdin, a FIFO or another program.
+//!
+//! Every line contains one action and is timestamped as soon as it is read, unless it
+//! starts with an explicit time. Empty lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! [TIME] uhm [CATEGORY]
+//! [TIME] undo
+//! [TIME] redo
+//! [TIME] pause
+//! [TIME] resume
+//! [TIME] toggle-pause
+//! [TIME] marker [LABEL]
+//! [TIME] speaker NAME
+//! [TIME] stop
+//! ```
+//!
+//! `TIME` is either a number of seconds since the start of the recording, like `12.5`,
+//! or an RFC 3339 timestamp like `2025-01-01T10:00:12.5Z`. Times must not go back, lines
+//! with a time before the previous event are skipped. The recording stops with `stop` or
+//! at the end of the stream.
+
+use std::{io::BufRead, sync::mpsc};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    Uhms,
+    record::{Action, RecordOptions, Recorder, SystemClock},
+};
+
+/// Record a session driven by the lines of `input`. See the [module](self) documentation
+/// for the format. Invalid lines are reported on stderr and skipped.
+///
+/// Blocks until the recording is stopped.
+pub fn record<R: BufRead + Send + 'static>(
+    input: R,
+    name: Option<String>,
+    notes: Option<String>,
+    options: &RecordOptions,
+) -> Uhms {
+    // relative times need to know the start before the recording runs
+    let mut options = options.clone();
+    let start = *options.start.get_or_insert_with(Utc::now);
+
+    let (sender, actions) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut previous = start;
+        for (i, line) in input.lines().enumerate() {
+            let Ok(line) = line else {
+                break;
+            };
+            let action = match parse_line(&line, start) {
+                Ok(Some(action)) => action,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("line {}: {}", i + 1, e);
+                    continue;
+                }
+            };
+            // offsets between events cannot be negative
+            if action.0 < previous {
+                eprintln!(
+                    "line {}: time {} lies before the previous event",
+                    i + 1,
+                    action.0.to_rfc3339()
+                );
+                continue;
+            }
+            previous = action.0;
+            let stop = action.1 == Action::Stop;
+            if sender.send(action).is_err() || stop {
+                break;
+            }
+        }
+    });
+
+    Recorder::new(SystemClock, actions, options)
+        .name(name)
+        .notes(notes)
+        .run()
+}
+
+/// Parse a single line of an event stream. Lines without an explicit time are
+/// timestamped with the current time, relative times are relative to `start`.
+///
+/// Returns `None` for empty lines and comments.
+pub fn parse_line(
+    line: &str,
+    start: DateTime<Utc>,
+) -> Result<Option<(DateTime<Utc>, Action)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (first, rest) = split_word(line);
+    let (time, line) = if let Ok(seconds) = first.parse::<f64>() {
+        let time = Some(seconds * 1e6)
+            .filter(|micros| micros.is_finite() && micros.abs() < i64::MAX as f64)
+            .and_then(|micros| start.checked_add_signed(TimeDelta::microseconds(micros as i64)));
+        match time {
+            Some(time) => (time, rest),
+            None => return Err(format!("time '{}' is out of range", first)),
+        }
+    } else if let Ok(time) = DateTime::parse_from_rfc3339(first) {
+        (time.to_utc(), rest)
+    } else {
+        (Utc::now(), line)
+    };
+
+    let (action, argument) = split_word(line);
+    let argument = if argument.is_empty() {
+        None
+    } else {
+        Some(argument.to_string())
+    };
+
+    let action = match action {
+        "uhm" => Action::Uhm { category: argument },
+        "undo" => Action::Undo,
+        "redo" => Action::Redo,
+        "pause" => Action::Pause,
+        "resume" => Action::Resume,
+        "toggle-pause" => Action::TogglePause,
+        "marker" => Action::Marker { label: argument },
+        "speaker" => match argument {
+            Some(name) => Action::Speaker { name },
+            None => return Err("missing speaker name".to_string()),
+        },
+        "stop" => Action::Stop,
+        "" => return Err("missing action".to_string()),
+        other => return Err(format!("unknown action '{}'", other)),
+    };
+
+    Ok(Some((time, action)))
+}
+
+/// Split a line into its first word and the trimmed remainder.
+fn split_word(line: &str) -> (&str, &str) {
+    match line.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (line, ""),
+    }
+}
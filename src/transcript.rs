@@ -0,0 +1,316 @@
+//! Detect filler words in transcripts with word timings, e.g. auto-generated subtitles.
+//!
+//! Transcripts are read as a list of [Word]s, either from SRT or WebVTT cues or from a
+//! JSON word list, and scanned for the phrases of a [Lexicon]. Every match becomes a uhm
+//! at the time of its first word, categorised by the lexicon.
+//!
+//! WebVTT cues may contain inline timestamps like `<00:00:01.500>` before each word. Words
+//! without an own timestamp are spread evenly across the time of their cue.
+//!
+//! JSON word lists contain objects with the text in `word` or `text` and the time in
+//! seconds in `start` and `end`. They are either given as a plain array, as the `words`
+//! of an object or as the `words` of its `segments`:
+//!
+//! ```json
+//! { "segments": [{ "words": [{ "word": "Uhm,", "start": 1.2, "end": 1.5 }] }] }
+//! ```
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    Uhms,
+    import::{self, Time},
+};
+
+/// Filler words and phrases of the default [Lexicon]. Each of them is its own category.
+pub const FILLERS: &[&str] = &[
+    "uh", "uhm", "um", "umm", "ah", "er", "erm", "hmm", "äh", "ähm", "like", "you know", "i mean",
+];
+
+/// A single word of a transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// The time at which the word starts in milliseconds relative to the start of the talk.
+    pub start: i64,
+    /// The time at which the word ends in milliseconds relative to the start of the talk.
+    pub end: i64,
+    pub text: String,
+}
+
+/// The format of a transcript file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// SRT or WebVTT cues.
+    Subtitles,
+    /// A JSON word list.
+    Json,
+}
+
+impl Format {
+    /// Guess the format from the extension of a file. Defaults to [Format::Subtitles].
+    pub fn detect(path: &str) -> Self {
+        match std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+        {
+            Some(e) if e.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Subtitles,
+        }
+    }
+}
+
+/// The filler phrases to search for, each with the category it is counted as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lexicon {
+    entries: Vec<(Vec<String>, String)>,
+}
+
+impl Default for Lexicon {
+    fn default() -> Self {
+        let mut lexicon = Self::new();
+        for filler in FILLERS {
+            lexicon.insert(filler, filler);
+        }
+        lexicon
+    }
+}
+
+impl Lexicon {
+    /// Create an empty lexicon.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add a phrase of one or more words, replacing its previous category. Phrases are
+    /// compared case-insensitively and without surrounding punctuation. An empty category
+    /// falls back to the phrase itself.
+    pub fn insert(&mut self, phrase: &str, category: &str) {
+        let words: Vec<String> = phrase.split_whitespace().filter_map(normalize).collect();
+        if words.is_empty() {
+            return;
+        }
+        let category = match category.trim() {
+            "" => words.join(" "),
+            category => category.to_string(),
+        };
+        self.entries.retain(|(w, _)| *w != words);
+        self.entries.push((words, category));
+    }
+
+    /// Parse an entry given as `PHRASE` or `PHRASE=CATEGORY` and add it.
+    pub fn insert_entry(&mut self, entry: &str) {
+        match entry.split_once('=') {
+            Some((phrase, category)) => self.insert(phrase, category.trim()),
+            None => self.insert(entry, entry.trim()),
+        }
+    }
+
+    /// Whether the lexicon has no phrases.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All phrases with their categories.
+    pub fn iter(&self) -> impl Iterator<Item = (String, &String)> {
+        self.entries
+            .iter()
+            .map(|(words, category)| (words.join(" "), category))
+    }
+
+    /// Find the longest phrase starting at the beginning of `words`. Returns its length
+    /// and category.
+    fn find(&self, words: &[String]) -> Option<(usize, &String)> {
+        self.entries
+            .iter()
+            .filter(|(phrase, _)| words.starts_with(phrase))
+            .max_by_key(|(phrase, _)| phrase.len())
+            .map(|(phrase, category)| (phrase.len(), category))
+    }
+}
+
+/// Lowercase a word and strip surrounding punctuation. Returns `None` if nothing is left.
+fn normalize(word: &str) -> Option<String> {
+    let word = word
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    (!word.is_empty()).then_some(word)
+}
+
+/// Parse the contents of a transcript file.
+pub fn parse(content: &str, format: Format) -> Result<Vec<Word>, String> {
+    match format {
+        Format::Subtitles => Ok(parse_subtitles(content)),
+        Format::Json => parse_json(content),
+    }
+}
+
+/// Read the words of all SRT or WebVTT cues.
+pub fn parse_subtitles(content: &str) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let Some((start, rest)) = line.split_once("-->") else {
+            continue;
+        };
+        // WebVTT allows cue settings after the end time
+        let end = rest.split_whitespace().next().unwrap_or_default();
+        let (Some(Time::Relative(start)), Some(Time::Relative(end))) =
+            (import::parse_time(start.trim()), import::parse_time(end))
+        else {
+            continue;
+        };
+
+        // split the cue into runs of text, each starting at an inline timestamp
+        let mut runs = vec![(start, String::new())];
+        for line in lines.by_ref() {
+            if line.trim().is_empty() {
+                break;
+            }
+            let mut rest = line;
+            while let Some(open) = rest.find('<') {
+                runs.last_mut().unwrap().1.push_str(&rest[..open]);
+                let Some(close) = rest[open..].find('>') else {
+                    rest = "";
+                    break;
+                };
+                if let Some(Time::Relative(time)) =
+                    import::parse_time(&rest[open + 1..open + close])
+                {
+                    runs.push((time, String::new()));
+                }
+                rest = &rest[open + close + 1..];
+            }
+            let text = &mut runs.last_mut().unwrap().1;
+            text.push_str(rest);
+            text.push(' ');
+        }
+
+        for (i, (from, text)) in runs.iter().enumerate() {
+            let to = runs
+                .get(i + 1)
+                .map(|(time, _)| *time)
+                .unwrap_or(end)
+                .max(*from);
+            let run: Vec<&str> = text.split_whitespace().collect();
+            let len = run.len() as i64;
+            for (j, word) in run.into_iter().enumerate() {
+                let j = j as i64;
+                words.push(Word {
+                    start: from + (to - from) * j / len,
+                    end: from + (to - from) * (j + 1) / len,
+                    text: word.to_string(),
+                });
+            }
+        }
+    }
+
+    words
+}
+
+/// Read a JSON word list. See the [module](self) documentation for the format.
+pub fn parse_json(content: &str) -> Result<Vec<Word>, String> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+
+    let lists: Vec<&serde_json::Value> = match &value {
+        serde_json::Value::Array(_) => vec![&value],
+        serde_json::Value::Object(object) => match (object.get("words"), object.get("segments")) {
+            (Some(words), _) => vec![words],
+            (None, Some(serde_json::Value::Array(segments))) => {
+                segments.iter().filter_map(|s| s.get("words")).collect()
+            }
+            _ => return Err("expected a list of words or segments".to_string()),
+        },
+        _ => return Err("expected a list of words or segments".to_string()),
+    };
+
+    let mut words = Vec::new();
+    for list in lists {
+        let Some(list) = list.as_array() else {
+            return Err("expected a list of words".to_string());
+        };
+        for word in list {
+            let text = word
+                .get("word")
+                .or_else(|| word.get("text"))
+                .and_then(|t| t.as_str());
+            let start = word.get("start").and_then(|t| t.as_f64());
+            let end = word.get("end").and_then(|t| t.as_f64());
+            let (Some(text), Some(start)) = (text, start) else {
+                return Err(format!("word {}: missing text or start", words.len() + 1));
+            };
+            let start = import::milliseconds(start);
+            let end = match end {
+                Some(end) => import::milliseconds(end),
+                None => start,
+            };
+            let (Some(start), Some(end)) = (start, end) else {
+                return Err(format!("word {}: time out of range", words.len() + 1));
+            };
+            words.push(Word {
+                start,
+                end,
+                text: text.trim().to_string(),
+            });
+        }
+    }
+
+    words.sort_by_key(|w| w.start);
+    Ok(words)
+}
+
+/// Find all phrases of the lexicon in a transcript. Returns the start of every match with
+/// its category. Longer phrases take precedence and matches do not overlap.
+pub fn detect(words: &[Word], lexicon: &Lexicon) -> Vec<(i64, String)> {
+    // keep the timing of every normalized word
+    let (times, normalized): (Vec<i64>, Vec<String>) = words
+        .iter()
+        .flat_map(|word| {
+            word.text
+                .split_whitespace()
+                .filter_map(normalize)
+                .map(move |text| (word.start, text))
+        })
+        .unzip();
+
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i < normalized.len() {
+        match lexicon.find(&normalized[i..]) {
+            Some((len, category)) => {
+                matches.push((times[i], category.clone()));
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+    matches
+}
+
+/// Build a categorised series from the filler phrases found in a transcript. The series
+/// starts at `start`, which defaults to the current time, and ends with the last word.
+pub fn to_uhms(words: &[Word], lexicon: &Lexicon, start: Option<DateTime<Utc>>) -> Uhms {
+    let start = start.unwrap_or_else(Utc::now);
+    let end = words.iter().map(|w| w.end.max(w.start)).max().unwrap_or(0);
+
+    let mut prev = 0;
+    let mut data = Vec::new();
+    let mut categories = Vec::new();
+    let mut found = detect(words, lexicon);
+    found.sort_by_key(|(offset, _)| *offset);
+    for (offset, category) in found {
+        data.push(offset - prev);
+        categories.push(Some(category));
+        prev = offset;
+    }
+
+    Uhms {
+        start,
+        end: start + TimeDelta::milliseconds(end),
+        data,
+        categories,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,321 @@
+//! Consistency checks for [Uhms] series.
+//!
+//! [Uhms::validate] checks a single series, [validate_all] additionally checks that the
+//! series of a data file can be told apart. Problems which can be repaired without
+//! guessing, like unsorted markers or an `end` before the last uhm, are repaired by
+//! [Uhms::fix] and [fix_all].
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Span, Uhms};
+
+/// How bad a [Problem] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The series can be used, but may not be what was intended.
+    Warning,
+    /// Stats of the series are wrong or cannot be calculated.
+    Error,
+}
+
+/// A single inconsistency of a series.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "problem", rename_all = "kebab-case")]
+pub enum Problem {
+    /// [Uhms::end] lies before [Uhms::start].
+    EndBeforeStart,
+    /// An offset in [Uhms::data] is negative.
+    NegativeOffset { index: usize, offset: i64 },
+    /// The offsets in [Uhms::data] add up to more than the duration.
+    PastEnd { overrun: i64 },
+    /// [Uhms::times] disagree with [Uhms::data], e.g. after editing the file by hand. Fixed by
+    /// deriving [Uhms::data] from the times again.
+    TimesMismatch,
+    /// [Uhms::categories] is neither empty nor as long as [Uhms::data].
+    CategoryCount { expected: usize, found: usize },
+    /// A pause ends before it starts or lies outside of the recording.
+    InvalidPause { index: usize },
+    /// Pauses are not ordered or overlap each other.
+    OverlappingPauses,
+    /// Markers are not ordered by their offset.
+    UnsortedMarkers,
+    /// A marker lies outside of the recording.
+    MarkerOutOfRange { index: usize },
+    /// Speaker turns are not ordered by their offset.
+    UnsortedTurns,
+    /// The planned duration is not positive.
+    InvalidPlan { planned: i64 },
+    /// The series has no [Uhms::id].
+    MissingId,
+    /// Another series has the same [Uhms::id].
+    DuplicateId { id: String },
+    /// Another series has the same name.
+    DuplicateName { name: String },
+}
+
+impl Problem {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::UnsortedMarkers
+            | Self::UnsortedTurns
+            | Self::MissingId
+            | Self::DuplicateName { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Whether [Uhms::fix] or [fix_all] can repair this problem without losing data.
+    pub fn is_fixable(&self) -> bool {
+        !matches!(
+            self,
+            Self::NegativeOffset { .. }
+                | Self::MarkerOutOfRange { .. }
+                | Self::InvalidPlan { .. }
+                | Self::DuplicateName { .. }
+        )
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EndBeforeStart => write!(f, "end lies before start"),
+            Self::NegativeOffset { index, offset } => {
+                write!(f, "offset {} is negative ({} ms)", index + 1, offset)
+            }
+            Self::PastEnd { overrun } => write!(f, "offsets add up to {} ms past the end", overrun),
+            Self::TimesMismatch => write!(f, "precise times do not match the offsets"),
+            Self::CategoryCount { expected, found } => {
+                write!(f, "found {} categories for {} uhms", found, expected)
+            }
+            Self::InvalidPause { index } => {
+                write!(f, "pause {} is invalid or out of range", index + 1)
+            }
+            Self::OverlappingPauses => write!(f, "pauses are unordered or overlap"),
+            Self::UnsortedMarkers => write!(f, "markers are not ordered"),
+            Self::MarkerOutOfRange { index } => {
+                write!(f, "marker {} lies outside of the recording", index + 1)
+            }
+            Self::UnsortedTurns => write!(f, "speaker turns are not ordered"),
+            Self::InvalidPlan { planned } => {
+                write!(f, "planned duration of {} ms is not positive", planned)
+            }
+            Self::MissingId => write!(f, "missing ID"),
+            Self::DuplicateId { id } => write!(f, "ID {} is used more than once", id),
+            Self::DuplicateName { name } => write!(f, "name '{}' is used more than once", name),
+        }
+    }
+}
+
+/// A problem found in the series at [Self::recording] of a data file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// The position of the series in the data file, starting at 0.
+    pub recording: usize,
+    pub severity: Severity,
+    pub fixable: bool,
+    #[serde(flatten)]
+    pub problem: Problem,
+}
+
+impl Diagnostic {
+    pub fn new(recording: usize, problem: Problem) -> Self {
+        Self {
+            recording,
+            severity: problem.severity(),
+            fixable: problem.is_fixable(),
+            problem,
+        }
+    }
+}
+
+impl Uhms {
+    /// Check this series for inconsistencies. The diagnostics refer to index 0.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        self.problems()
+            .into_iter()
+            .map(|p| Diagnostic::new(0, p))
+            .collect()
+    }
+
+    fn problems(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        let duration = self.duration().num_milliseconds();
+
+        if duration < 0 {
+            problems.push(Problem::EndBeforeStart);
+        }
+        for (index, offset) in self.data.iter().enumerate() {
+            if *offset < 0 {
+                problems.push(Problem::NegativeOffset {
+                    index,
+                    offset: *offset,
+                });
+            }
+        }
+        let last = self.data.iter().sum::<i64>();
+        if duration >= 0 && last > duration {
+            problems.push(Problem::PastEnd {
+                overrun: last - duration,
+            });
+        }
+        if let Some(times) = &self.times
+            && times.to_data() != self.data
+        {
+            problems.push(Problem::TimesMismatch);
+        }
+        if !self.categories.is_empty() && self.categories.len() != self.data.len() {
+            problems.push(Problem::CategoryCount {
+                expected: self.data.len(),
+                found: self.categories.len(),
+            });
+        }
+
+        let end = duration.max(last);
+        for (index, pause) in self.pauses.iter().enumerate() {
+            if pause.start < 0 || pause.end < pause.start || pause.end > end {
+                problems.push(Problem::InvalidPause { index });
+            }
+        }
+        if self.pauses.windows(2).any(|w| w[1].start < w[0].end) {
+            problems.push(Problem::OverlappingPauses);
+        }
+
+        if self.markers.windows(2).any(|w| w[1].offset < w[0].offset) {
+            problems.push(Problem::UnsortedMarkers);
+        }
+        for (index, marker) in self.markers.iter().enumerate() {
+            if marker.offset < 0 || marker.offset > end {
+                problems.push(Problem::MarkerOutOfRange { index });
+            }
+        }
+        if self.turns.windows(2).any(|w| w[1].offset < w[0].offset) {
+            problems.push(Problem::UnsortedTurns);
+        }
+
+        if let Some(planned) = self.planned
+            && planned <= 0
+        {
+            problems.push(Problem::InvalidPlan { planned });
+        }
+        if self.id.is_empty() {
+            problems.push(Problem::MissingId);
+        }
+
+        problems
+    }
+
+    /// Repair all fixable problems of this series, see [Problem::is_fixable]. A missing ID
+    /// is only repaired by [fix_all], which knows the IDs of the other series.
+    ///
+    /// Returns the number of repaired problems.
+    pub fn fix(&mut self) -> usize {
+        let mut fixed = 0;
+        // the offsets are derived from the precise times, so the other fixes depend on them
+        if let Some(times) = self.times.clone()
+            && times.to_data() != self.data
+        {
+            self.set_times(times);
+            fixed += 1;
+        }
+        for problem in self.problems() {
+            match problem {
+                Problem::EndBeforeStart | Problem::PastEnd { .. } => {
+                    // the recording lasts at least until its last uhm
+                    let last = self.data.iter().sum::<i64>().max(0);
+                    self.end = self.start + chrono::TimeDelta::milliseconds(last);
+                }
+                Problem::CategoryCount { expected, .. } => {
+                    self.categories.resize(expected, None);
+                }
+                Problem::InvalidPause { .. } | Problem::OverlappingPauses => self.fix_pauses(),
+                Problem::UnsortedMarkers => self.markers.sort_by_key(|m| m.offset),
+                Problem::UnsortedTurns => self.turns.sort_by_key(|t| t.offset),
+                _ => continue,
+            }
+            fixed += 1;
+        }
+        fixed
+    }
+
+    /// Clamp pauses to the recording, drop empty ones and merge overlapping ones.
+    fn fix_pauses(&mut self) {
+        let end = self.duration().num_milliseconds().max(0);
+        let mut pauses: Vec<Span> = self
+            .pauses
+            .iter()
+            .map(|p| Span {
+                start: p.start.clamp(0, end),
+                end: p.end.clamp(0, end),
+            })
+            .filter(|p| !p.is_empty())
+            .collect();
+        pauses.sort_by_key(|p| p.start);
+
+        let mut merged: Vec<Span> = Vec::with_capacity(pauses.len());
+        for pause in pauses {
+            match merged.last_mut() {
+                Some(last) if pause.start <= last.end => last.end = last.end.max(pause.end),
+                _ => merged.push(pause),
+            }
+        }
+        self.pauses = merged;
+    }
+}
+
+/// Check all series of a data file, including problems between them like duplicate IDs
+/// or names.
+pub fn validate_all(recordings: &[Uhms]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (index, uhm) in recordings.iter().enumerate() {
+        diagnostics.extend(
+            uhm.problems()
+                .into_iter()
+                .map(|p| Diagnostic::new(index, p)),
+        );
+
+        let earlier = &recordings[..index];
+        if !uhm.id.is_empty() && earlier.iter().any(|other| other.id == uhm.id) {
+            diagnostics.push(Diagnostic::new(
+                index,
+                Problem::DuplicateId { id: uhm.id.clone() },
+            ));
+        }
+        if let Some(name) = &uhm.name
+            && earlier
+                .iter()
+                .any(|other| other.name.as_ref() == Some(name))
+        {
+            diagnostics.push(Diagnostic::new(
+                index,
+                Problem::DuplicateName { name: name.clone() },
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+/// Repair all fixable problems of a data file. Series with a duplicate ID except for the
+/// first one get a new ID.
+///
+/// Returns the number of repaired problems.
+pub fn fix_all(recordings: &mut [Uhms]) -> usize {
+    let mut fixed = 0;
+
+    for index in 0..recordings.len() {
+        fixed += recordings[index].fix();
+        let id = &recordings[index].id;
+        if recordings[..index].iter().any(|other| other.id == *id) {
+            recordings[index].id.clear();
+        }
+        if recordings[index].id.is_empty() {
+            fixed += 1;
+        }
+    }
+    crate::schema::assign_ids(recordings);
+
+    fixed
+}
@@ -0,0 +1,289 @@
+mod common;
+
+use std::process::{Command, Output};
+
+use chrono::{DateTime, TimeDelta};
+use common::TempDir;
+use uhm::{Uhms, io, schema};
+
+/// Run the command line tool with `args`.
+fn uhm(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_uhm"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn recording(id: &str, name: &str) -> Uhms {
+    let start = DateTime::from_timestamp(1_735_689_600, 0).unwrap();
+    Uhms {
+        id: id.to_string(),
+        start,
+        end: start + TimeDelta::minutes(5),
+        data: vec![1000, 2000],
+        name: Some(name.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Write a data file of `recordings` to `path`.
+fn write(path: &str, recordings: Vec<Uhms>) {
+    std::fs::write(path, schema::to_string(&recordings).unwrap()).unwrap();
+}
+
+/// Read the recordings of a data file.
+fn read(path: &str) -> Vec<Uhms> {
+    let content = std::fs::read_to_string(path).unwrap();
+    schema::parse(&content).unwrap().0.recordings
+}
+
+/// The IDs of the recordings `stats` reads from `path` with `args`, or what it printed on
+/// failure.
+fn selected(path: &str, args: &[&str]) -> Result<Vec<String>, String> {
+    let output = uhm(&[&["-f", path, "stats", "--json"], args].concat());
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !output.status.success() {
+        return Err(stdout);
+    }
+    let stats: Vec<serde_json::Value> = serde_json::from_str(&stdout).unwrap();
+    Ok(stats
+        .iter()
+        .map(|stats| stats["id"].as_str().unwrap().to_string())
+        .collect())
+}
+
+#[test]
+fn rejects_negative_tolerances() {
+    for command in ["merge", "stats"] {
+        for tolerance in ["-t=-1", "-t=NaN", "-t=inf", "-t=soon"] {
+            let output = uhm(&["-f", "missing.json", command, tolerance, "a", "b"]);
+
+            assert!(!output.status.success());
+            assert!(String::from_utf8_lossy(&output.stderr).contains("invalid tolerance"));
+        }
+    }
+}
+
+#[test]
+fn selects_recordings_by_id_prefix_name_or_index() {
+    let dir = TempDir::new("select");
+    let file = dir.file("uhm.json");
+    write(
+        &file,
+        vec![
+            recording("1a2b", "intro"),
+            recording("1a3c", "1"),
+            recording("77f0", "outro"),
+        ],
+    );
+
+    assert_eq!(
+        selected(&file, &["1a2b", "outro"]).unwrap(),
+        ["1a2b", "77f0"]
+    );
+    assert_eq!(selected(&file, &["3"]).unwrap(), ["77f0"]);
+    // names come before indices and all-digit ID prefixes out of range before errors
+    assert_eq!(selected(&file, &["1"]).unwrap(), ["1a3c"]);
+    assert_eq!(selected(&file, &["77"]).unwrap(), ["77f0"]);
+
+    assert!(selected(&file, &["1a"]).unwrap_err().contains("ambiguous"));
+    assert!(
+        selected(&file, &["4"])
+            .unwrap_err()
+            .contains("No recording at index 4")
+    );
+    assert!(
+        selected(&file, &["keynote"])
+            .unwrap_err()
+            .contains("No recording matches 'keynote'")
+    );
+}
+
+/// Recordings a day apart with different metadata.
+fn presentations() -> Vec<Uhms> {
+    let mut ann = recording("a1", "welcome");
+    ann.meta.speaker = Some("Ann".to_string());
+    ann.meta.tags = vec!["demo".to_string()];
+    let mut bob = recording("b2", "keynote");
+    bob.start += TimeDelta::days(1);
+    bob.end += TimeDelta::days(1);
+    bob.meta.speaker = Some("Bob".to_string());
+    bob.meta.tags = vec!["demo".to_string(), "long".to_string()];
+    bob.meta.rehearsal = true;
+    let mut anonymous = recording("c3", "closing");
+    anonymous.start += TimeDelta::days(2);
+    anonymous.end += TimeDelta::days(2);
+    vec![ann, bob, anonymous]
+}
+
+#[test]
+fn filters_recordings_by_metadata_and_start() {
+    let dir = TempDir::new("filter");
+    let file = dir.file("uhm.json");
+    write(&file, presentations());
+
+    assert_eq!(selected(&file, &["-w", "speaker=Ann"]).unwrap(), ["a1"]);
+    assert_eq!(selected(&file, &["-w", "tag=demo"]).unwrap(), ["a1", "b2"]);
+    assert_eq!(
+        selected(&file, &["-w", "tag=demo", "-w", "speaker=Bob"]).unwrap(),
+        ["b2"]
+    );
+    // an empty value matches recordings without one
+    assert_eq!(selected(&file, &["-w", "speaker="]).unwrap(), ["c3"]);
+    assert_eq!(selected(&file, &["-w", "name=closing"]).unwrap(), ["c3"]);
+    assert_eq!(selected(&file, &["-w", "rehearsal=true"]).unwrap(), ["b2"]);
+
+    assert_eq!(
+        selected(&file, &["--since", "2025-01-02 00:00:00"]).unwrap(),
+        ["b2", "c3"]
+    );
+    assert_eq!(
+        selected(&file, &["--before", "2025-01-02 00:00:00"]).unwrap(),
+        ["a1"]
+    );
+    assert_eq!(
+        selected(
+            &file,
+            &[
+                "--since",
+                "2025-01-02 00:00:00",
+                "--before",
+                "2025-01-03 00:00:00"
+            ]
+        )
+        .unwrap(),
+        ["b2"]
+    );
+
+    assert!(
+        selected(&file, &["-w", "colour=red"])
+            .unwrap_err()
+            .contains("unknown metadata key 'colour'")
+    );
+    assert!(
+        selected(&file, &["-w", "speaker"])
+            .unwrap_err()
+            .contains("expected KEY=VALUE")
+    );
+}
+
+#[test]
+fn groups_stats_by_metadata() {
+    let dir = TempDir::new("group");
+    let file = dir.file("uhm.json");
+    write(&file, presentations());
+
+    let output = uhm(&["-f", &file, "stats", "--json", "--group-by", "tag"]);
+    assert!(output.status.success());
+    let groups: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let groups: Vec<(Option<&str>, u64, u64)> = groups
+        .iter()
+        .map(|g| {
+            (
+                g["name"].as_str(),
+                g["recordings"].as_u64().unwrap(),
+                g["count"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    // recordings with several tags count towards each, those without any come last
+    assert_eq!(
+        groups,
+        [(Some("demo"), 2, 4), (Some("long"), 1, 2), (None, 1, 2)]
+    );
+
+    let output = uhm(&["-f", &file, "stats", "-g", "colour"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn edits_metadata() {
+    let dir = TempDir::new("edit");
+    let file = dir.file("uhm.json");
+    write(&file, presentations());
+
+    let output = uhm(&[
+        "-f",
+        &file,
+        "edit",
+        "a1",
+        "b2",
+        "--presenter",
+        "",
+        "--event",
+        "Conf",
+        "--tag",
+        "demo",
+        "--tag",
+        "short",
+        "--untag",
+        "long",
+        "--audience",
+        "30",
+        "--no-rehearsal",
+    ]);
+    assert!(output.status.success());
+
+    let edited = read(&file);
+    for uhm in &edited[..2] {
+        // empty values unset a key and tags are only added once
+        assert_eq!(uhm.meta.speaker, None);
+        assert_eq!(uhm.meta.event.as_deref(), Some("Conf"));
+        assert_eq!(uhm.meta.audience, Some(30));
+        assert!(!uhm.meta.rehearsal);
+    }
+    assert_eq!(edited[0].meta.tags, ["demo", "short"]);
+    assert_eq!(edited[1].meta.tags, ["demo", "short"]);
+    assert!(edited[2].meta.is_empty());
+
+    let output = uhm(&["-f", &file, "edit", "c3", "--rehearsal"]);
+    assert!(output.status.success());
+    assert!(read(&file)[2].meta.rehearsal);
+
+    let output = uhm(&["-f", &file, "edit", "c3", "--rehearsal", "--no-rehearsal"]);
+    assert!(!output.status.success());
+}
+
+/// The names of the recordings in a data file.
+fn names(path: &str) -> Vec<String> {
+    read(path).into_iter().filter_map(|uhm| uhm.name).collect()
+}
+
+#[test]
+fn restores_backups() {
+    let dir = TempDir::new("restore");
+    let file = dir.file("uhm.json");
+    write(&file, vec![recording("c3", "current")]);
+    write(&io::backup_path(&file, 1), vec![recording("b2", "newer")]);
+    write(
+        &io::backup_path(&file, 2),
+        vec![recording("a1", "older"), recording("a2", "oldest")],
+    );
+
+    let output = uhm(&["-f", &file, "restore", "--list"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(&format!(" 1. {} (1 recordings", io::backup_path(&file, 1))));
+    assert!(lines[1].starts_with(&format!(" 2. {} (2 recordings", io::backup_path(&file, 2))));
+
+    let output = uhm(&["-f", &file, "restore", "3"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No backup 3"));
+    let output = uhm(&["-f", &file, "restore", "0"]);
+    assert!(!output.status.success());
+    assert_eq!(names(&file), ["current"]);
+
+    let output = uhm(&["-f", &file, "restore", "2"]);
+    assert!(output.status.success());
+    assert_eq!(names(&file), ["older", "oldest"]);
+    // the current file becomes the newest backup, so restoring can be undone
+    assert_eq!(names(&io::backup_path(&file, 1)), ["current"]);
+    assert_eq!(names(&io::backup_path(&file, 2)), ["newer"]);
+    assert_eq!(names(&io::backup_path(&file, 3)), ["older", "oldest"]);
+
+    let output = uhm(&["-f", &file, "restore"]);
+    assert!(output.status.success());
+    assert_eq!(names(&file), ["current"]);
+}
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+/// A temporary directory, removed with all its contents when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create an empty directory. `name` must be unique among the tests of a file.
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("uhm-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    /// The path of this directory.
+    pub fn path(&self) -> String {
+        self.0.to_string_lossy().into_owned()
+    }
+
+    /// The path of a file in this directory.
+    pub fn file(&self, name: &str) -> String {
+        format!("{}/{}", self.path(), name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
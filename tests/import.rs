@@ -0,0 +1,124 @@
+use chrono::{DateTime, TimeDelta};
+use uhm::{
+    import::{self, Format, Stamp, Time},
+    transcript::Lexicon,
+};
+
+fn parse(content: &str, format: Format) -> Result<Vec<Stamp>, String> {
+    let mut lexicon = Lexicon::new();
+    lexicon.insert("uhm", "uhm");
+    import::parse(content, format, &lexicon)
+}
+
+fn times(stamps: &[Stamp]) -> Vec<Time> {
+    stamps.iter().map(|s| s.time).collect()
+}
+
+#[test]
+fn reads_relative_and_absolute_times() {
+    let start = DateTime::from_timestamp(1_735_725_600, 0).unwrap();
+    assert_eq!(import::parse_time("12.5"), Some(Time::Relative(12_500)));
+    assert_eq!(import::parse_time("1:02.5"), Some(Time::Relative(62_500)));
+    assert_eq!(
+        import::parse_time("01:02:03,250"),
+        Some(Time::Relative(3_723_250))
+    );
+    assert_eq!(
+        import::parse_time("2025-01-01T10:00:00Z"),
+        Some(Time::Absolute(start))
+    );
+    assert_eq!(
+        import::parse_time("2025-01-01 11:00:00 +0100"),
+        Some(Time::Absolute(start))
+    );
+    assert_eq!(
+        import::parse_time("2025-01-01 10:00:00"),
+        Some(Time::Absolute(start))
+    );
+    for invalid in [
+        "",
+        "soon",
+        "-1",
+        "1:-2",
+        "1:2:3:4",
+        "inf",
+        "1e20",
+        "1000000000000:00",
+    ] {
+        assert_eq!(import::parse_time(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn reads_text_with_categories() {
+    let stamps = parse(
+        "# review\n0:12.5 uhm\n\n1:03\n2025-01-01 10:02:30 ah\n",
+        Format::Text,
+    )
+    .unwrap();
+
+    assert_eq!(stamps.len(), 3);
+    assert_eq!(stamps[0].category.as_deref(), Some("uhm"));
+    assert_eq!(
+        stamps[1],
+        Stamp {
+            time: Time::Relative(63_000),
+            category: None
+        }
+    );
+    assert_eq!(stamps[2].category.as_deref(), Some("ah"));
+    assert!(matches!(stamps[2].time, Time::Absolute(_)));
+}
+
+#[test]
+fn reads_csv_with_a_header() {
+    let stamps = parse("time,category\n12.5,uhm\n\"63\",\n", Format::Csv).unwrap();
+
+    assert_eq!(
+        times(&stamps),
+        vec![Time::Relative(12_500), Time::Relative(63_000)]
+    );
+    assert_eq!(stamps[1].category, None);
+}
+
+#[test]
+fn reads_fillers_from_srt() {
+    let content =
+        "1\n00:00:01,000 --> 00:00:02,000\nSo uhm yes\n\n2\n00:00:05,000 --> 00:00:06,000\nuhm\n";
+    let stamps = parse(content, Format::Subtitles).unwrap();
+
+    assert_eq!(
+        times(&stamps),
+        vec![Time::Relative(1_333), Time::Relative(5_000)]
+    );
+}
+
+#[test]
+fn names_the_line_of_invalid_timestamps() {
+    assert_eq!(
+        parse("1.5\nsoon\n", Format::Text),
+        Err("line 2: invalid timestamp 'soon'".to_string())
+    );
+    assert_eq!(
+        parse("1e20 uhm\n", Format::Text),
+        Err("line 1: invalid timestamp '1e20'".to_string())
+    );
+    assert_eq!(
+        parse("time\n1\n1e300,uhm\n", Format::Csv),
+        Err("line 3: invalid timestamp '1e300'".to_string())
+    );
+}
+
+#[test]
+fn builds_uhms_from_stamps() {
+    let start = DateTime::from_timestamp(1_735_725_600, 0).unwrap();
+    let stamps = parse("3\n1 ah\n", Format::Text).unwrap();
+
+    let uhm = import::to_uhms(&stamps, Some(start), Some(TimeDelta::seconds(10))).unwrap();
+    assert_eq!(uhm.data, vec![1000, 2000]);
+    assert_eq!(uhm.categories, vec![Some("ah".to_string()), None]);
+    assert_eq!(uhm.end, start + TimeDelta::seconds(10));
+
+    assert!(import::to_uhms(&stamps, Some(start), Some(TimeDelta::seconds(2))).is_err());
+    assert!(import::to_uhms(&stamps, Some(start), Some(TimeDelta::MAX)).is_err());
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::TempDir;
+use uhm::io::{self, FileLock, WriteTarget};
+
+#[test]
+fn rotates_backups() {
+    let dir = TempDir::new("backups");
+    let path = dir.file("uhm.json");
+    let target = WriteTarget::File(path.clone());
+    for content in ["1", "2", "3", "4"] {
+        target.replace(content, 2).unwrap();
+    }
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "4");
+    assert_eq!(
+        io::backups(&path),
+        vec![io::backup_path(&path, 1), io::backup_path(&path, 2)]
+    );
+    assert_eq!(
+        std::fs::read_to_string(io::backup_path(&path, 1)).unwrap(),
+        "3"
+    );
+    assert_eq!(
+        std::fs::read_to_string(io::backup_path(&path, 2)).unwrap(),
+        "2"
+    );
+}
+
+#[test]
+fn locks_files_exclusively() {
+    let dir = TempDir::new("lock");
+    let path = dir.file("uhm.json");
+    let lock = FileLock::acquire(&path).unwrap();
+    assert!(FileLock::try_acquire(&path).unwrap().is_none());
+
+    drop(lock);
+    assert!(FileLock::try_acquire(&path).unwrap().is_some());
+}
+
+#[test]
+fn expands_directories_and_patterns() {
+    let temp = TempDir::new("data-files");
+    let dir = temp.path();
+    for name in [
+        "b.json",
+        "a.jsonl",
+        "a.json.bak.1",
+        ".a.json.lock",
+        "notes.txt",
+    ] {
+        std::fs::write(format!("{}/{}", dir, name), "").unwrap();
+    }
+
+    let files = io::data_files(&dir).unwrap();
+    assert_eq!(
+        files,
+        vec![format!("{}/a.jsonl", dir), format!("{}/b.json", dir)]
+    );
+    assert_eq!(
+        io::data_files(&format!("{}/?.json", dir)).unwrap(),
+        vec![format!("{}/b.json", dir)]
+    );
+    assert!(io::data_files(&format!("{}/*.db", dir)).unwrap().is_empty());
+    assert_eq!(
+        io::data_files("missing.json").unwrap(),
+        vec!["missing.json"]
+    );
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, TimeDelta};
+use uhm::{Uhms, merge, validate::Problem};
+
+fn observed(id: &str, data: Vec<i64>) -> Uhms {
+    let start = DateTime::from_timestamp(1_735_689_600, 0).unwrap();
+    Uhms {
+        id: id.to_string(),
+        start,
+        end: start + TimeDelta::seconds(10),
+        data,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn keeps_consensus_of_overlapping_groups_in_order() {
+    // the group of 0 ms and 1900 ms averages to 950 ms, after the lone uhm at 100 ms
+    let first = observed("a", vec![0, 100]);
+    let second = observed("b", vec![1900]);
+    let merged = merge::merge(&[&first, &second], TimeDelta::seconds(2), 1);
+
+    assert_eq!(merged.data, vec![100, 850]);
+    // the merged recording only lacks an ID until it is stored
+    assert!(
+        merged
+            .validate()
+            .iter()
+            .all(|d| d.problem == Problem::MissingId)
+    );
+}
+
+#[test]
+fn names_the_compared_recordings() {
+    let first = observed("a", vec![1000, 1000]);
+    let second = observed("b", vec![1100]);
+    let agreement = merge::agreement(&first, &second, TimeDelta::milliseconds(500));
+
+    assert_eq!(agreement.reference.id, "a");
+    assert_eq!(agreement.other.id, "b");
+    assert_eq!(
+        (agreement.matched, agreement.missed, agreement.extra),
+        (1, 1, 0)
+    );
+}
@@ -0,0 +1,456 @@
+use std::{sync::mpsc, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use console::Key;
+use uhm::{
+    Marker, Precision, Span, Turn, Uhms,
+    config::Config,
+    record::{Action, Journal, ManualClock, RecordOptions, Recorder, ScriptedSource},
+};
+
+fn start() -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(1_700_000_000_000).unwrap()
+}
+
+fn replay(keys: &[(i64, Key)], options: RecordOptions) -> Uhms {
+    let clock = ManualClock::new(start());
+    let source = ScriptedSource::keys(clock.clone(), keys, &options);
+    Recorder::new(clock, source, options).display(false).run()
+}
+
+#[test]
+fn records_offsets_between_keys() {
+    let uhm = replay(
+        &[
+            (1000, Key::Char('u')),
+            (2500, Key::Char('u')),
+            (4000, Key::Char('x')),
+            (5000, Key::Enter),
+        ],
+        RecordOptions::default(),
+    );
+
+    assert_eq!(uhm.start, start());
+    assert_eq!(uhm.end, start() + TimeDelta::milliseconds(5000));
+    assert_eq!(uhm.data, vec![1000, 1500, 1500]);
+    assert!(uhm.categories.is_empty());
+}
+
+#[test]
+fn assigns_categories_by_key() {
+    let mut options = RecordOptions::default();
+    options.categories.insert('u', "uhm".to_string());
+    options.categories.insert('a', "ah".to_string());
+
+    let uhm = replay(
+        &[
+            (1000, Key::Char('u')),
+            (2000, Key::Char('a')),
+            (3000, Key::Char('x')),
+            (4000, Key::Enter),
+        ],
+        options,
+    );
+
+    assert_eq!(
+        uhm.categories,
+        vec![Some("uhm".to_string()), Some("ah".to_string()), None]
+    );
+    let stats = uhm.stats();
+    assert_eq!(stats.categories.len(), 3);
+    assert_eq!(stats.categories[0].count, 1);
+}
+
+#[test]
+fn undo_merges_offset_into_next_uhm() {
+    let uhm = replay(
+        &[
+            (1000, Key::Char('u')),
+            (2000, Key::Char('u')),
+            (2100, Key::Backspace),
+            (3000, Key::Char('u')),
+            (4000, Key::Enter),
+        ],
+        RecordOptions::default(),
+    );
+
+    assert_eq!(uhm.data, vec![1000, 2000]);
+}
+
+#[test]
+fn redo_restores_undone_uhm_at_its_time() {
+    let uhm = replay(
+        &[
+            (1000, Key::Char('u')),
+            (2000, Key::Char('u')),
+            (2100, Key::Backspace),
+            (2200, Key::Del),
+            (3000, Key::Enter),
+        ],
+        RecordOptions::default(),
+    );
+
+    assert_eq!(uhm.data, vec![1000, 1000]);
+}
+
+#[test]
+fn redo_is_discarded_by_new_uhm() {
+    let uhm = replay(
+        &[
+            (1000, Key::Char('u')),
+            (2000, Key::Backspace),
+            (3000, Key::Char('u')),
+            (3500, Key::Del),
+            (4000, Key::Enter),
+        ],
+        RecordOptions::default(),
+    );
+
+    assert_eq!(uhm.data, vec![3000]);
+}
+
+#[test]
+fn pauses_are_excluded_from_duration() {
+    let uhm = replay(
+        &[
+            (1000, Key::Char('u')),
+            (2000, Key::Tab),
+            (3000, Key::Char('u')),
+            (32000, Key::Tab),
+            (33000, Key::Char('u')),
+            (62000, Key::Enter),
+        ],
+        RecordOptions::default(),
+    );
+
+    assert_eq!(uhm.data, vec![1000, 32000]);
+    assert_eq!(
+        uhm.pauses,
+        vec![Span {
+            start: 2000,
+            end: 32000
+        }]
+    );
+    assert_eq!(uhm.active_duration(), TimeDelta::seconds(32));
+    assert_eq!(uhm.active_data(), vec![1000, 2000]);
+    assert!((uhm.stats().per_minute - 2. / (32. / 60.)).abs() < 1e-9);
+}
+
+#[test]
+fn markers_split_sections() {
+    let uhm = replay(
+        &[
+            (1000, Key::Char('u')),
+            (10000, Key::ArrowRight),
+            (11000, Key::Char('u')),
+            (12000, Key::Char('u')),
+            (20000, Key::Enter),
+        ],
+        RecordOptions::default(),
+    );
+
+    assert_eq!(
+        uhm.markers,
+        vec![Marker {
+            offset: 10000,
+            label: None
+        }]
+    );
+    let sections = uhm.stats().sections;
+    assert_eq!(sections.len(), 2);
+    assert_eq!(sections[0].count, 1);
+    assert_eq!(sections[1].count, 2);
+}
+
+#[test]
+fn stops_at_planned_duration() {
+    let options = RecordOptions {
+        duration: Some(TimeDelta::seconds(10)),
+        ..Default::default()
+    };
+    let uhm = replay(
+        &[
+            (1000, Key::Char('u')),
+            (12000, Key::Char('u')),
+            (13000, Key::Enter),
+        ],
+        options,
+    );
+
+    assert_eq!(uhm.data, vec![1000]);
+    assert_eq!(uhm.end, start() + TimeDelta::seconds(10));
+    assert_eq!(uhm.planned, Some(10000));
+    assert_eq!(uhm.overtime(), Some(TimeDelta::zero()));
+}
+
+#[test]
+fn stops_at_planned_duration_without_a_stop() {
+    let clock = ManualClock::new(start());
+    let options = RecordOptions {
+        start: Some(start()),
+        duration: Some(TimeDelta::seconds(10)),
+        refresh: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let (sender, actions) = mpsc::channel();
+    sender
+        .send((
+            start() + TimeDelta::seconds(1),
+            Action::Uhm { category: None },
+        ))
+        .unwrap();
+    clock.advance(TimeDelta::seconds(12));
+
+    // the source stays open, so only the planned duration can stop the recording
+    let uhm = Recorder::new(clock, actions, options).display(false).run();
+    drop(sender);
+
+    assert_eq!(uhm.data, vec![1000]);
+    assert_eq!(uhm.end, start() + TimeDelta::seconds(10));
+}
+
+#[test]
+fn keeps_recording_in_overtime() {
+    let options = RecordOptions {
+        duration: Some(TimeDelta::seconds(10)),
+        overtime: true,
+        ..Default::default()
+    };
+    let uhm = replay(
+        &[
+            (1000, Key::Char('u')),
+            (12000, Key::Char('u')),
+            (13000, Key::Enter),
+        ],
+        options,
+    );
+
+    assert_eq!(uhm.data, vec![1000, 11000]);
+    assert_eq!(uhm.overtime(), Some(TimeDelta::seconds(3)));
+}
+
+#[test]
+fn closed_source_stops_recording() {
+    let clock = ManualClock::new(start());
+    let actions = vec![
+        (
+            start() + TimeDelta::seconds(1),
+            Action::Uhm { category: None },
+        ),
+        (
+            start() + TimeDelta::seconds(2),
+            Action::Marker {
+                label: Some("Q&A".to_string()),
+            },
+        ),
+    ];
+    let source = ScriptedSource::new(clock.clone(), actions);
+    let uhm = Recorder::new(clock, source, RecordOptions::default())
+        .name(Some("talk".to_string()))
+        .display(false)
+        .run();
+
+    assert_eq!(uhm.name.as_deref(), Some("talk"));
+    assert_eq!(uhm.end, start() + TimeDelta::seconds(2));
+    assert_eq!(uhm.markers[0].label.as_deref(), Some("Q&A"));
+}
+
+#[test]
+fn honours_configured_bindings() {
+    let config = Config::parse(
+        r#"
+        count_other_keys = false
+        [keys]
+        Space = "count"
+        u = "category:uhm"
+        Escape = "stop"
+        Enter = "none"
+        "#,
+    )
+    .unwrap();
+    let options = RecordOptions {
+        bindings: config.bindings,
+        ..Default::default()
+    };
+
+    let uhm = replay(
+        &[
+            (1000, Key::Char(' ')),
+            (2000, Key::Char('u')),
+            (3000, Key::Char('x')),
+            (4000, Key::Enter),
+            (5000, Key::Escape),
+        ],
+        options,
+    );
+
+    assert_eq!(uhm.data, vec![1000, 1000]);
+    assert_eq!(uhm.categories, vec![None, Some("uhm".to_string())]);
+    assert_eq!(uhm.end, start() + TimeDelta::seconds(5));
+}
+
+#[test]
+fn attributes_uhms_to_speakers() {
+    let options = RecordOptions {
+        speakers: vec!["Ann".to_string(), "Bob".to_string()],
+        ..Default::default()
+    };
+
+    let uhm = replay(
+        &[
+            (10000, Key::Char('u')),
+            (30000, Key::Char('2')),
+            (40000, Key::Char('u')),
+            (45000, Key::Char('u')),
+            (50000, Key::Tab),
+            (70000, Key::Tab),
+            (80000, Key::Char('1')),
+            (90000, Key::Enter),
+        ],
+        options,
+    );
+
+    assert_eq!(
+        uhm.turns,
+        vec![
+            Turn {
+                offset: 0,
+                speaker: "Ann".to_string()
+            },
+            Turn {
+                offset: 30000,
+                speaker: "Bob".to_string()
+            },
+            Turn {
+                offset: 80000,
+                speaker: "Ann".to_string()
+            },
+        ]
+    );
+    assert_eq!(uhm.speaker(1).map(String::as_str), Some("Bob"));
+
+    let speakers = uhm.stats().speakers;
+    assert_eq!(speakers.len(), 2);
+    assert_eq!(speakers[0].name.as_deref(), Some("Ann"));
+    assert_eq!(speakers[0].count, 1);
+    assert_eq!(speakers[0].min_sec, (0, 40.));
+    assert_eq!(speakers[1].count, 2);
+    // Bob talked for 50 seconds minus a 20 second pause
+    assert_eq!(speakers[1].min_sec, (0, 30.));
+    assert!((speakers[1].per_minute - 4.).abs() < 1e-9);
+}
+
+/// A journal file of its own for every test, which is removed again by the test.
+fn journal_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("uhm-test-{}-{}.journal", name, std::process::id()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn recovers_recordings_from_journals() {
+    let path = journal_path("recover");
+    let mut journal = Journal::create(
+        &path,
+        start(),
+        Some("talk".to_string()),
+        None,
+        Some(TimeDelta::seconds(60)),
+        Precision::Milliseconds,
+    )
+    .unwrap();
+    let at = |ms| start() + TimeDelta::milliseconds(ms);
+    journal
+        .append(at(1000), &Action::Uhm { category: None })
+        .unwrap();
+    journal.append(at(2000), &Action::Pause).unwrap();
+    journal.append(at(5000), &Action::Resume).unwrap();
+    journal
+        .append(at(6000), &Action::Uhm { category: None })
+        .unwrap();
+    journal.append(at(7000), &Action::Undo).unwrap();
+    journal
+        .append(at(8500), &Action::Uhm { category: None })
+        .unwrap();
+    // the recording is interrupted before it is stopped
+
+    let uhm = Journal::recover(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(uhm.name.as_deref(), Some("talk"));
+    assert_eq!(uhm.start, start());
+    assert_eq!(uhm.end, at(8500));
+    assert_eq!(uhm.data, vec![1000, 7500]);
+    assert_eq!(
+        uhm.pauses,
+        vec![Span {
+            start: 2000,
+            end: 5000
+        }]
+    );
+    assert_eq!(uhm.planned, Some(60000));
+}
+
+#[test]
+fn recovers_journals_with_a_truncated_last_line() {
+    use std::io::Write;
+
+    let path = journal_path("truncated");
+    let mut journal =
+        Journal::create(&path, start(), None, None, None, Precision::Milliseconds).unwrap();
+    let at = |ms| start() + TimeDelta::milliseconds(ms);
+    journal
+        .append(at(1000), &Action::Uhm { category: None })
+        .unwrap();
+    journal
+        .append(at(3000), &Action::Uhm { category: None })
+        .unwrap();
+    drop(journal);
+    // the recording was killed while writing the next action
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(br#"{"offset":40"#).unwrap();
+    drop(file);
+
+    let uhm = Journal::recover(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(uhm.data, vec![1000, 2000]);
+    assert_eq!(uhm.end, at(3000));
+}
+
+#[test]
+fn recovers_journals_in_their_precision() {
+    let path = journal_path("precision");
+    let start = start() + TimeDelta::microseconds(250);
+    let clock = ManualClock::new(start);
+    let actions = vec![
+        (
+            start + TimeDelta::microseconds(1_500),
+            Action::Uhm { category: None },
+        ),
+        (
+            start + TimeDelta::microseconds(2_750),
+            Action::Uhm { category: None },
+        ),
+        (start + TimeDelta::microseconds(4_001), Action::Stop),
+    ];
+    let source = ScriptedSource::new(clock.clone(), actions);
+    let options = RecordOptions {
+        journal: Some(path.clone()),
+        precision: Precision::Microseconds,
+        ..Default::default()
+    };
+    let recorded = Recorder::new(clock, source, options).display(false).run();
+
+    let recovered = Journal::recover(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(recovered.start, start);
+    assert_eq!(recovered.end, recorded.end);
+    assert_eq!(recovered.times, recorded.times);
+    assert_eq!(recovered.times.unwrap().offsets, vec![1_500, 2_750]);
+}
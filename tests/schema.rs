@@ -0,0 +1,183 @@
+use uhm::{
+    Precision, Times,
+    schema::{self, Format, VERSION},
+    validate::Problem,
+};
+
+const LEGACY: &str = r#"[
+  {
+    "start": "2025-01-01 10:00:00 +0000",
+    "end": "2025-01-01 10:01:00 +0000",
+    "data": [1000, 2000],
+    "name": "talk",
+    "notes": null
+  }
+]"#;
+
+#[test]
+fn migrates_legacy_arrays() {
+    let (document, version) = schema::parse(LEGACY).unwrap();
+
+    assert_eq!(version, 1);
+    assert_eq!(document.version, VERSION);
+    assert_eq!(document.recordings.len(), 1);
+    assert_eq!(document.recordings[0].data, vec![1000, 2000]);
+}
+
+#[test]
+fn writes_current_version() {
+    let (document, _) = schema::parse(LEGACY).unwrap();
+    let written = schema::to_string(&document.recordings).unwrap();
+
+    let (reread, version) = schema::parse(&written).unwrap();
+    assert_eq!(version, VERSION);
+    assert_eq!(reread.recordings[0].name.as_deref(), Some("talk"));
+}
+
+#[test]
+fn rejects_newer_versions() {
+    let newer = format!(r#"{{"version": {}, "recordings": []}}"#, VERSION + 1);
+    assert!(schema::parse(&newer).is_err());
+}
+
+#[test]
+fn backfills_stable_unique_ids() {
+    let twice = LEGACY.replacen("[", &format!("[{},", &LEGACY[1..LEGACY.len() - 1]), 1);
+    let (first, _) = schema::parse(&twice).unwrap();
+    let (second, _) = schema::parse(&twice).unwrap();
+
+    assert_eq!(first.recordings.len(), 2);
+    assert_eq!(first.recordings[0].id.len(), 12);
+    assert_ne!(first.recordings[0].id, first.recordings[1].id);
+    assert_eq!(first.recordings[0].id, second.recordings[0].id);
+    assert_eq!(first.recordings[1].id, second.recordings[1].id);
+}
+
+#[test]
+fn validates_and_fixes_recordings() {
+    let (mut document, _) = schema::parse(LEGACY).unwrap();
+    let uhm = &mut document.recordings[0];
+    uhm.end = uhm.start - chrono::TimeDelta::seconds(1);
+    uhm.categories = vec![Some("uhm".to_string())];
+
+    let problems: Vec<_> = uhm.validate().into_iter().map(|d| d.problem).collect();
+    assert_eq!(
+        problems,
+        vec![
+            Problem::EndBeforeStart,
+            Problem::CategoryCount {
+                expected: 2,
+                found: 1
+            }
+        ]
+    );
+
+    assert_eq!(uhm.fix(), 2);
+    assert!(uhm.validate().is_empty());
+    assert_eq!(uhm.duration().num_milliseconds(), 3000);
+    assert_eq!(uhm.categories, vec![Some("uhm".to_string()), None]);
+}
+
+#[test]
+fn keeps_precise_times() {
+    let (mut document, _) = schema::parse(LEGACY).unwrap();
+    let uhm = &mut document.recordings[0];
+    uhm.start += chrono::TimeDelta::microseconds(250);
+    let times = vec![
+        uhm.start + chrono::TimeDelta::microseconds(1_000_600),
+        uhm.start + chrono::TimeDelta::microseconds(3_000_500),
+    ];
+    uhm.set_event_times(&times, Precision::Microseconds);
+    assert_eq!(uhm.data, vec![1000, 2000]);
+
+    let written = schema::to_string(&document.recordings).unwrap();
+    assert!(written.contains("2025-01-01 10:00:00.000250 +0000"));
+    let (reread, _) = schema::parse(&written).unwrap();
+    let uhm = &reread.recordings[0];
+    assert_eq!(uhm.event_times(), times);
+    assert_eq!(
+        uhm.times(Precision::Milliseconds),
+        Times::from_data(&uhm.data, Precision::Milliseconds)
+    );
+    assert!(uhm.validate().is_empty());
+}
+
+#[test]
+fn writes_start_and_end_in_the_precision_of_the_times() {
+    let (mut document, _) = schema::parse(LEGACY).unwrap();
+    document.recordings[0].start += chrono::TimeDelta::nanoseconds(1_234_567);
+
+    let written = schema::to_string(&document.recordings).unwrap();
+
+    assert!(written.contains("2025-01-01 10:00:00.001 +0000"));
+    assert!(written.contains("2025-01-01 10:01:00.000 +0000"));
+}
+
+#[test]
+fn derives_offsets_from_mismatching_times() {
+    let (mut document, _) = schema::parse(LEGACY).unwrap();
+    let uhm = &mut document.recordings[0];
+    uhm.set_times(Times {
+        precision: Precision::Microseconds,
+        offsets: vec![1_000_500, 3_000_500],
+    });
+    uhm.data = vec![1000, 5000];
+
+    let problems: Vec<_> = uhm.validate().into_iter().map(|d| d.problem).collect();
+    assert_eq!(problems, vec![Problem::TimesMismatch]);
+
+    assert_eq!(uhm.fix(), 1);
+    assert!(uhm.validate().is_empty());
+    assert_eq!(uhm.data, vec![1000, 2000]);
+    assert_eq!(uhm.precision(), Precision::Microseconds);
+}
+
+#[test]
+fn converts_offsets_losslessly() {
+    let data = vec![0, 1500, 20, 7];
+    let times = Times::from_data(&data, Precision::Nanoseconds);
+
+    assert_eq!(
+        times.offsets,
+        vec![0, 1_500_000_000, 1_520_000_000, 1_527_000_000]
+    );
+    assert_eq!(times.to_data(), data);
+    assert_eq!(
+        times.with_precision(Precision::Milliseconds).offsets,
+        vec![0, 1500, 1520, 1527]
+    );
+}
+
+#[test]
+fn clamps_spans_out_of_range() {
+    use chrono::TimeDelta;
+
+    assert_eq!(Precision::Nanoseconds.from_delta(TimeDelta::MIN), -i64::MAX);
+    assert_eq!(Precision::Nanoseconds.from_delta(TimeDelta::MAX), i64::MAX);
+    assert_eq!(
+        Precision::Microseconds.from_delta(TimeDelta::MIN),
+        -i64::MAX
+    );
+    assert_eq!(
+        Precision::Milliseconds.from_delta(TimeDelta::MIN),
+        -i64::MAX
+    );
+    assert_eq!(
+        Precision::Microseconds.from_delta(TimeDelta::nanoseconds(-1)),
+        -1
+    );
+}
+
+#[test]
+fn reads_json_lines() {
+    let (document, _) = schema::parse(LEGACY).unwrap();
+    let lines = schema::to_lines(&document.recordings).unwrap();
+    assert_eq!(Format::detect(&lines), Format::Lines);
+    assert_eq!(Format::detect(LEGACY), Format::Json);
+
+    let appended = lines.clone() + &schema::to_line(&document.recordings[0]).unwrap();
+    let (reread, version) = schema::parse(&appended).unwrap();
+    assert_eq!(version, VERSION);
+    assert_eq!(reread.recordings.len(), 2);
+    assert_eq!(reread.recordings[1].data, vec![1000, 2000]);
+}
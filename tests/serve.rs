@@ -0,0 +1,56 @@
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+};
+
+use uhm::{record::RecordOptions, serve};
+
+/// Send a request and return the status line of the response.
+fn request(addr: SocketAddr, request: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response.lines().next().unwrap_or_default().to_string()
+}
+
+fn post(addr: SocketAddr, target: &str) -> String {
+    request(
+        addr,
+        &format!("POST {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target),
+    )
+}
+
+#[test]
+fn records_taps_sent_over_http() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let recording = std::thread::spawn(move || {
+        serve::record_on(
+            listener,
+            Some("talk".to_string()),
+            None,
+            &RecordOptions::default(),
+        )
+    });
+
+    // an idle connection must not block the taps after it
+    let _idle = TcpStream::connect(addr).unwrap();
+
+    assert_eq!(post(addr, "/uhm"), "HTTP/1.1 200 OK");
+    assert_eq!(post(addr, "/uhm?category=ah"), "HTTP/1.1 200 OK");
+    assert_eq!(post(addr, "/uhm"), "HTTP/1.1 200 OK");
+    assert_eq!(post(addr, "/undo"), "HTTP/1.1 200 OK");
+    assert_eq!(post(addr, "/nothing"), "HTTP/1.1 404 Not Found");
+    let huge = "POST /uhm HTTP/1.1\r\nContent-Length: 10000000000\r\n\r\n";
+    assert_eq!(request(addr, huge), "HTTP/1.1 413 Content Too Large");
+    assert_eq!(post(addr, "/stop"), "HTTP/1.1 200 OK");
+
+    let uhm = recording.join().unwrap();
+    assert_eq!(uhm.name.as_deref(), Some("talk"));
+    assert_eq!(uhm.data.len(), 2);
+    assert_eq!(uhm.categories, vec![None, Some("ah".to_string())]);
+
+    // the server stops with the recording
+    assert!(TcpListener::bind(addr).is_ok());
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "sqlite")]
+
+mod common;
+
+use chrono::{DateTime, TimeDelta};
+use common::TempDir;
+use uhm::{Uhms, io, query::Query, schema, sqlite::Database, store::Store};
+
+fn recording(name: &str, day: i64, tags: &[&str]) -> Uhms {
+    let start = DateTime::from_timestamp(1_735_689_600 + day * 86_400, 0).unwrap();
+    let mut uhm = Uhms {
+        start,
+        end: start + TimeDelta::minutes(5),
+        data: vec![1000, 2000],
+        name: Some(name.to_string()),
+        ..Default::default()
+    };
+    uhm.meta.tags = tags.iter().map(|t| t.to_string()).collect();
+    uhm
+}
+
+#[test]
+fn saves_and_queries_recordings() {
+    let dir = TempDir::new("sqlite");
+    let path = dir.file("uhm.db");
+
+    let mut recordings = vec![
+        recording("intro", 0, &["demo"]),
+        recording("keynote", 1, &[]),
+        recording("intro", 2, &["demo", "remote"]),
+    ];
+    schema::assign_ids(&mut recordings);
+    let mut database = Database::open(&path).unwrap();
+    database.replace(recordings[..2].to_vec()).unwrap();
+    database.insert(recordings[2].clone()).unwrap();
+
+    let (document, version) = Database::open(&path).unwrap().load().unwrap();
+    assert_eq!(version, schema::VERSION);
+    let ids: Vec<&String> = document.recordings.iter().map(|r| &r.id).collect();
+    assert_eq!(ids, recordings.iter().map(|r| &r.id).collect::<Vec<_>>());
+
+    let query = Query {
+        name: Some("intro".to_string()),
+        since: Some(recordings[1].start),
+        tags: vec!["demo".to_string()],
+        ..Default::default()
+    };
+    let found = database.query(&query).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, recordings[2].id);
+    assert!(query.matches(&recordings[2]) && !query.matches(&recordings[0]));
+}
+
+#[test]
+fn keeps_backups_on_failed_updates() {
+    let dir = TempDir::new("sqlite-backups");
+    let path = dir.file("uhm.db");
+    let mut recordings = vec![recording("intro", 0, &[])];
+    schema::assign_ids(&mut recordings);
+    Database::open(&path)
+        .unwrap()
+        .replace(recordings.clone())
+        .unwrap();
+
+    let mut database = Database::open(&path).unwrap().backups(2);
+    let mut unknown = recordings[0].clone();
+    unknown.id = "unknown".to_string();
+    assert!(database.update(unknown).is_err());
+    assert!(io::backups(&path).is_empty());
+
+    database.update(recordings[0].clone()).unwrap();
+    assert_eq!(io::backups(&path), vec![io::backup_path(&path, 1)]);
+}
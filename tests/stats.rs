@@ -0,0 +1,27 @@
+use chrono::{DateTime, TimeDelta};
+use uhm::{Marker, Uhms};
+
+#[test]
+fn empty_sections_have_no_rate() {
+    let start = DateTime::from_timestamp_millis(1_700_000_000_000).unwrap();
+    let marker = Marker {
+        offset: 2000,
+        ..Default::default()
+    };
+    let uhm = Uhms {
+        start,
+        end: start + TimeDelta::seconds(3),
+        data: vec![1000],
+        markers: vec![marker.clone(), marker],
+        ..Default::default()
+    };
+
+    let sections = uhm.stats().sections;
+    assert_eq!(sections.len(), 3);
+    assert_eq!(sections[1].per_minute, 0.);
+    assert!(
+        serde_json::to_string(&uhm.stats())
+            .unwrap()
+            .contains("\"per_minute\":0.0")
+    );
+}
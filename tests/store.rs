@@ -0,0 +1,134 @@
+mod common;
+
+use chrono::{DateTime, TimeDelta};
+use common::TempDir;
+use uhm::{
+    Uhms,
+    query::Query,
+    schema::Format,
+    store::{FileStore, MemoryStore, MultiStore, Store},
+};
+
+fn recording(name: &str) -> Uhms {
+    let start = DateTime::from_timestamp(1_735_689_600, 0).unwrap();
+    Uhms {
+        start,
+        end: start + TimeDelta::minutes(5),
+        data: vec![1000, 2000],
+        name: Some(name.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn changes_recordings_by_id() {
+    let mut store = MemoryStore::default();
+    let intro = store.insert(recording("intro")).unwrap();
+    let keynote = store.insert(recording("keynote")).unwrap();
+    assert_ne!(intro, keynote);
+
+    let mut duplicate = recording("intro");
+    duplicate.id = intro.clone();
+    assert!(store.insert(duplicate).is_err());
+
+    let mut renamed = store.get(&intro).unwrap().unwrap();
+    renamed.name = Some("welcome".to_string());
+    store.update(renamed).unwrap();
+    let query = Query {
+        name: Some("welcome".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(store.query(&query).unwrap().len(), 1);
+
+    assert_eq!(
+        store.delete(&keynote).unwrap().map(|uhm| uhm.id),
+        Some(keynote.clone())
+    );
+    assert!(store.delete(&keynote).unwrap().is_none());
+    assert!(store.update(recording("keynote")).is_err());
+    let names: Vec<_> = store
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|uhm| uhm.name)
+        .collect();
+    assert_eq!(names, vec![Some("welcome".to_string())]);
+}
+
+#[test]
+fn appends_to_json_lines() {
+    let dir = TempDir::new("store");
+    let path = dir.file("uhm.jsonl");
+
+    let mut store = FileStore::open(&path).unwrap();
+    store.insert(recording("intro")).unwrap();
+    store.insert(recording("keynote")).unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(Format::detect(&content), Format::Lines);
+    assert_eq!(content.lines().count(), 3);
+
+    let ids = |store: &FileStore| {
+        store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|uhm| uhm.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&FileStore::open(&path).unwrap()), ids(&store));
+}
+
+#[test]
+fn changes_recordings_in_their_own_store() {
+    let ann = MemoryStore::new(vec![recording("intro")]);
+    let bob = MemoryStore::new(vec![recording("keynote"), recording("outro")]);
+    let mut stores = MultiStore::default()
+        .with("ann.json", Box::new(ann))
+        .with("bob.json", Box::new(bob));
+
+    let recordings = stores.list().unwrap();
+    assert_eq!(recordings.len(), 3);
+    let keynote = recordings[1].clone();
+    let sources = |stores: &MultiStore| {
+        let recordings = stores.list_with_sources().unwrap();
+        recordings
+            .into_iter()
+            .map(|(source, uhm)| (source, uhm.id))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        sources(&stores)[0],
+        ("ann.json".to_string(), recordings[0].id.clone())
+    );
+    assert_eq!(
+        sources(&stores)[1],
+        ("bob.json".to_string(), keynote.id.clone())
+    );
+
+    assert!(stores.insert(recording("demo")).is_err());
+    assert!(stores.delete(&keynote.id).unwrap().is_some());
+    assert_eq!(stores.list().unwrap().len(), 2);
+    assert!(sources(&stores).iter().all(|(_, id)| *id != keynote.id));
+}
+
+#[test]
+fn keeps_the_source_of_recordings_in_identical_files() {
+    let stores = MultiStore::default()
+        .with(
+            "ann.json",
+            Box::new(MemoryStore::new(vec![recording("intro")])),
+        )
+        .with(
+            "copy.json",
+            Box::new(MemoryStore::new(vec![recording("intro")])),
+        );
+
+    let recordings = stores.query_with_sources(&Query::default()).unwrap();
+
+    let sources: Vec<&str> = recordings
+        .iter()
+        .map(|(source, _)| source.as_str())
+        .collect();
+    assert_eq!(sources, vec!["ann.json", "copy.json"]);
+    assert_eq!(recordings[0].1.id, recordings[1].1.id);
+}
@@ -0,0 +1,28 @@
+use std::io::Cursor;
+
+use chrono::{DateTime, TimeDelta};
+use uhm::{record::RecordOptions, stream};
+
+#[test]
+fn rejects_times_out_of_range() {
+    let start = DateTime::from_timestamp(1_735_689_600, 0).unwrap();
+
+    let (time, _) = stream::parse_line("1.5 uhm", start).unwrap().unwrap();
+    assert_eq!(time, start + TimeDelta::milliseconds(1500));
+    for line in ["1e20 uhm", "-1e300 uhm", "inf uhm", "NaN uhm"] {
+        assert!(stream::parse_line(line, start).is_err(), "{}", line);
+    }
+}
+
+#[test]
+fn skips_times_going_back() {
+    let options = RecordOptions {
+        start: DateTime::from_timestamp(1_735_689_600, 0),
+        ..Default::default()
+    };
+    let input = Cursor::new("1 uhm\n0.5 uhm\n2 uhm\n-1 uhm\n3 stop\n");
+    let uhm = stream::record(input, None, None, &options);
+
+    assert_eq!(uhm.data, vec![1000, 1000]);
+    assert_eq!(uhm.duration(), TimeDelta::seconds(3));
+}
@@ -0,0 +1,88 @@
+use uhm::transcript::{self, Lexicon, Word};
+
+fn lexicon(entries: &[&str]) -> Lexicon {
+    let mut lexicon = Lexicon::new();
+    for entry in entries {
+        lexicon.insert_entry(entry);
+    }
+    lexicon
+}
+
+#[test]
+fn reads_inline_word_timings_from_webvtt() {
+    let words = transcript::parse_subtitles(
+        "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\n<00:00:01.000><c>So,</c> <00:00:01.600><c>uhm</c>\n",
+    );
+
+    assert_eq!(
+        words,
+        vec![
+            Word {
+                start: 1000,
+                end: 1600,
+                text: "So,".to_string()
+            },
+            Word {
+                start: 1600,
+                end: 2000,
+                text: "uhm".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn detects_longest_phrases_with_their_categories() {
+    let words = transcript::parse_json(
+        r#"[
+            {"word": " Uhm,", "start": 0.5, "end": 0.8},
+            {"word": " you", "start": 1.0, "end": 1.1},
+            {"word": " know", "start": 1.1, "end": 1.3},
+            {"word": " ÄH.", "start": 2.0, "end": 2.2}
+        ]"#,
+    )
+    .unwrap();
+    let lexicon = lexicon(&["uhm", "you", "you know=you know", "äh=uhm"]);
+
+    let uhm = transcript::to_uhms(&words, &lexicon, None);
+
+    assert_eq!(uhm.data, vec![500, 500, 1000]);
+    assert_eq!(
+        uhm.categories,
+        vec![
+            Some("uhm".to_string()),
+            Some("you know".to_string()),
+            Some("uhm".to_string())
+        ]
+    );
+    assert_eq!(uhm.duration().num_milliseconds(), 2200);
+}
+
+#[test]
+fn detects_filler_phrases_by_default() {
+    let words = transcript::parse_subtitles(
+        "00:00:01,000 --> 00:00:04,000\nSo, uhm, it was like, you know, I mean fine.\n",
+    );
+
+    let fillers = transcript::detect(&words, &Lexicon::default());
+
+    let categories: Vec<&str> = fillers
+        .iter()
+        .map(|(_, category)| category.as_str())
+        .collect();
+    assert_eq!(categories, vec!["uhm", "like", "you know", "i mean"]);
+}
+
+#[test]
+fn counts_entries_without_a_category_as_their_phrase() {
+    let words = transcript::parse_subtitles("00:00:01,000 --> 00:00:02,000\nUm, you know.\n");
+    let lexicon = lexicon(&["um=", "You  Know = "]);
+
+    let fillers = transcript::detect(&words, &lexicon);
+
+    let categories: Vec<&str> = fillers
+        .iter()
+        .map(|(_, category)| category.as_str())
+        .collect();
+    assert_eq!(categories, vec!["um", "you know"]);
+}